@@ -0,0 +1,42 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use wobj::Obj;
+
+/// Grid of `size` x `size` quads per object
+fn grid_objects(objects: usize, size: usize) -> String {
+    let mut text = String::new();
+    for y in 0..=size {
+        for x in 0..=size {
+            text += &format!("v {x} {y} 0\n");
+        }
+    }
+    for object in 0..objects {
+        text += &format!("o Grid{object}\n");
+        for y in 0..size {
+            for x in 0..size {
+                let i = y * (size + 1) + x + 1;
+                text += &format!("f {} {} {} {}\n", i, i + 1, i + size + 2, i + size + 1);
+            }
+        }
+    }
+    text
+}
+
+fn triangulate(c: &mut Criterion) {
+    let obj = Obj::parse(grid_objects(64, 64).as_bytes()).unwrap();
+
+    let mut group = c.benchmark_group("64 objects of 64x64 quads");
+    group.sample_size(20);
+    group.bench_function("triangulate", |b| {
+        b.iter(|| {
+            obj.meshes()
+                .iter()
+                .map(|mesh| mesh.triangulate())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("par_triangulate", |b| b.iter(|| obj.par_triangulate()));
+    group.finish();
+}
+
+criterion_group!(benches, triangulate);
+criterion_main!(benches);
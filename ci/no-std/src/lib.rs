@@ -0,0 +1,17 @@
+#![no_std]
+
+/// Parses an OBJ and counts the triangles of its meshes
+pub fn triangle_count(bytes: &[u8]) -> Result<usize, wobj::WobjError> {
+    let obj = wobj::Obj::parse(bytes)?;
+    let mut count = 0;
+    for mesh in obj.meshes() {
+        let (indices, _) = mesh.triangulate()?;
+        count += indices.0.len() / 3;
+    }
+    Ok(count)
+}
+
+/// Parses an MTL and returns its material count
+pub fn material_count(bytes: &[u8]) -> Result<usize, wobj::WobjError> {
+    Ok(wobj::Mtl::parse(bytes)?.len())
+}
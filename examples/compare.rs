@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use wobj::{Obj, ParseOptions, ParseStats};
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {error}");
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args_os().skip(1).map(PathBuf::from);
+    let (Some(first), Some(second)) = (args.next(), args.next()) else {
+        return Err("Usage: compare <first.obj> <second.obj>".into());
+    };
+
+    let first_stats = parse(&first)?;
+    let second_stats = parse(&second)?;
+
+    let ratio = second_stats.throughput() / first_stats.throughput();
+    println!(
+        "{} parses at {:.2}x the throughput of {}",
+        second.display(),
+        ratio,
+        first.display()
+    );
+
+    Ok(())
+}
+
+fn parse(path: &PathBuf) -> Result<ParseStats, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    let (_, stats) = Obj::parse_with_stats(&bytes, ParseOptions::default())?;
+
+    println!("OBJ: ({})", path.display());
+    for line in stats.to_string().lines() {
+        println!("  {line}");
+    }
+    println!();
+
+    Ok(stats)
+}
@@ -13,29 +13,32 @@ fn run() -> Result<(), Box<dyn Error>> {
     let path = PathBuf::from(std::env::args_os().nth(1).expect("No path was specified!"));
 
     let obj_file = std::fs::read(&path)?;
-    let now = Instant::now();
-    let obj = wobj::Obj::parse(&obj_file)?;
-    let load_time = now.elapsed();
+    let (obj, stats) = wobj::Obj::parse_with_stats(&obj_file, Default::default())?;
 
     println!("OBJ: ({})", path.display());
-    println!("  Parsed in {} seconds", load_time.as_secs_f64());
-    println!("  Vertices:  {}", obj.vertices().len());
-    println!("  Normals:  {}", obj.normals().len());
-    println!("  UVs:  {}", obj.uvs().len());
+    println!(
+        "  Parsed in {} seconds ({:.1} MB/s)",
+        stats.duration.as_secs_f64(),
+        stats.throughput() / 1e6
+    );
+    for line in obj.summary().to_string().lines() {
+        println!("  {line}");
+    }
 
     // Print OBJ object stats and collect MTL files
     println!("  Objects:");
     let mut mtl_files = HashSet::new();
     for mesh in obj.meshes() {
         println!(
-            "    {}: material: {}, face count: {}",
+            "    {}: material: {}, face count: {}, triangle count: {}",
             mesh.name().unwrap_or("<none>"),
             mesh.material().unwrap_or("<none>"),
-            mesh.faces().len()
+            mesh.faces().len(),
+            mesh.triangle_count()
         );
 
         if let Some(mtllib) = mesh.mtllib() {
-            mtl_files.insert(mtllib.to_path_buf());
+            mtl_files.insert(mtllib.to_owned());
         }
     }
 
@@ -49,12 +52,12 @@ fn run() -> Result<(), Box<dyn Error>> {
         let mtl = wobj::Mtl::parse(&file)?;
         let load_time = now.elapsed();
 
-        println!("MTL: ({})", mtl_path.display());
+        println!("MTL: ({mtl_path})");
         println!("  Parsed in {} seconds", load_time.as_secs_f64());
-        println!("  Material count: {}", mtl.inner().len());
+        println!("  Material count: {}", mtl.len());
 
         println!("  Materials:");
-        for name in mtl.inner().keys() {
+        for (name, _) in mtl.iter() {
             println!("    {}", name);
         }
 
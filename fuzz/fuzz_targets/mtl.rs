@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wobj::Mtl;
+
+fuzz_target!(|bytes: &[u8]| {
+    // Whatever the input, parsing must not panic
+    let _ = Mtl::parse(bytes);
+
+    // Text skips the UTF-8 validation of names, debug builds still assert it
+    if let Ok(text) = str::from_utf8(bytes) {
+        let _ = Mtl::parse_str(text);
+    }
+});
@@ -0,0 +1,41 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wobj::{Grouping, InvalidUtf8, Obj, ParseOptions};
+
+fuzz_target!(|input: (u8, u8, &[u8])| {
+    // The grouping uses `flags % 3`, which depends on every bit of `flags`
+    let (flags, more_flags, bytes) = input;
+    let options = ParseOptions {
+        grouping: match flags % 3 {
+            0 => Grouping::Attributes,
+            1 => Grouping::Object,
+            _ => Grouping::Group,
+        },
+        strict: flags & 0x04 != 0,
+        raw_indices: flags & 0x08 != 0,
+        comment_fallback: flags & 0x10 != 0,
+        capture_comments: flags & 0x20 != 0,
+        keep_empty_objects: flags & 0x40 != 0,
+        uv_w: flags & 0x80 != 0,
+        forward_references: more_flags & 0x01 != 0,
+        on_invalid_utf8: InvalidUtf8::Lossy,
+    };
+
+    // Whatever the input, parsing and using the result must not panic
+    if let Ok(obj) = Obj::parse_with(bytes, options) {
+        obj.validate();
+        obj.hierarchy();
+        for mesh in obj.meshes() {
+            let _ = mesh.triangulate();
+            let _ = mesh.indexed();
+        }
+    }
+    let _ = Obj::parse_lossy(bytes, options);
+    let _ = Obj::parse_events(bytes, |_| ());
+
+    // Text skips the UTF-8 validation of names, debug builds still assert it
+    if let Ok(text) = str::from_utf8(bytes) {
+        let _ = Obj::parse_str(text);
+    }
+});
@@ -0,0 +1,19 @@
+//! Items shared by the builds with and without the `std` feature
+//!
+//! Public types must not depend on the feature, so that enabling `std`
+//! anywhere in a dependency graph does not break `no_std` users.
+
+pub use alloc::borrow::{Cow, ToOwned};
+pub use alloc::boxed::Box;
+pub use alloc::string::{String, ToString};
+pub use alloc::sync::Arc;
+pub use alloc::vec::Vec;
+pub use alloc::{format, vec};
+
+/// Hasher of all maps, seeded randomly only with `std`
+pub use ahash::RandomState;
+
+/// Insertion ordered map, available with and without `std`
+pub type Map<K, V> = indexmap::IndexMap<K, V, RandomState>;
+/// Insertion ordered set, available with and without `std`
+pub type Set<T> = indexmap::IndexSet<T, RandomState>;
@@ -1,24 +1,133 @@
-use std::fmt::Display;
+use core::fmt::Display;
+
+use winnow::error::{ContextError, StrContext, StrContextValue};
+
+use crate::compat::*;
 
 #[derive(Debug)]
-pub struct WobjError(String);
+pub struct WobjError {
+    message: String,
+    labels: Vec<&'static str>,
+    expected: Vec<String>,
+    #[cfg(feature = "std")]
+    io: Option<std::io::Error>,
+}
+
+impl WobjError {
+    /// Labels of what was being parsed when the error occurred, innermost first
+    ///
+    /// For example `"vertex geometry"` or `"diffuse (Kd)"`. Empty for errors
+    /// which did not come from the parser.
+    pub fn labels(&self) -> &[&'static str] {
+        &self.labels
+    }
+
+    /// Forms the input could have taken at the error, like `"r g b"`
+    ///
+    /// Alternatives are listed in the order they were tried, so a tool can
+    /// present them as "expected one of".
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    #[cfg(feature = "std")]
+    /// The I/O error which caused this error, like a file failing to open or map
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        self.io.as_ref()
+    }
+
+    /// Keeps the context of a parser error with another message
+    pub(crate) fn with_context(message: String, error: &ContextError) -> Self {
+        let mut labels = Vec::new();
+        let mut expected = Vec::new();
+        for context in error.context() {
+            match context {
+                StrContext::Label(label) => labels.push(*label),
+                StrContext::Expected(StrContextValue::CharLiteral(c)) => {
+                    expected.push(c.to_string())
+                }
+                StrContext::Expected(StrContextValue::StringLiteral(s)) => {
+                    expected.push(s.to_string())
+                }
+                StrContext::Expected(StrContextValue::Description(s)) => {
+                    expected.push(s.to_string())
+                }
+                _ => (),
+            }
+        }
+        Self {
+            message,
+            labels,
+            expected,
+            #[cfg(feature = "std")]
+            io: None,
+        }
+    }
+
+    /// Replaces the message, keeping the context
+    pub(crate) fn map_message(mut self, f: impl FnOnce(String) -> String) -> Self {
+        self.message = f(self.message);
+        self
+    }
+}
+
+impl<I: winnow::stream::AsBStr> From<winnow::error::ParseError<I, ContextError>> for WobjError {
+    #[cfg(feature = "std")]
+    fn from(error: winnow::error::ParseError<I, ContextError>) -> Self {
+        Self::with_context(format!("{error}"), error.inner())
+    }
 
-impl<I: winnow::stream::AsBStr, E: Display> From<winnow::error::ParseError<I, E>> for WobjError {
-    fn from(error: winnow::error::ParseError<I, E>) -> Self {
-        Self(format!("{error}"))
+    /// Without std winnow renders no line and drops the external cause
+    #[cfg(not(feature = "std"))]
+    fn from(error: winnow::error::ParseError<I, ContextError>) -> Self {
+        let input: &[u8] = error.input().as_bstr();
+        let line = crate::util::LineCounter::new(input).line(&input[error.offset()..]);
+        let message = format!("parse error at line {line}: {}", error.inner());
+        Self::with_context(message, error.inner())
     }
 }
 
 impl From<&str> for WobjError {
     fn from(value: &str) -> Self {
-        Self(value.to_string())
+        Self::from(value.to_string())
+    }
+}
+
+impl From<String> for WobjError {
+    fn from(value: String) -> Self {
+        Self {
+            message: value,
+            labels: Vec::new(),
+            expected: Vec::new(),
+            #[cfg(feature = "std")]
+            io: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for WobjError {
+    fn from(error: std::io::Error) -> Self {
+        let message = error.to_string();
+        Self {
+            io: Some(error),
+            ..Self::from(message)
+        }
     }
 }
 
-impl std::fmt::Display for WobjError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+impl Display for WobjError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.message)
     }
 }
 
-impl std::error::Error for WobjError {}
+impl core::error::Error for WobjError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        #[cfg(feature = "std")]
+        if let Some(error) = &self.io {
+            return Some(error);
+        }
+        None
+    }
+}
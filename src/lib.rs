@@ -1,10 +1,26 @@
-#![doc = include_str!("../README.md")]
+#![cfg_attr(feature = "std", doc = include_str!("../README.md"))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("wobj needs the `std` or the `libm` feature for float math");
+
+mod compat;
 mod error;
+mod math;
 mod mtl;
 mod obj;
+mod options;
+#[cfg(feature = "ply")]
+mod ply;
 mod util;
+mod warning;
 
 pub use error::WobjError;
 pub use mtl::*;
 pub use obj::*;
+pub use options::*;
+#[cfg(feature = "ply")]
+pub use ply::PlyFormat;
+pub use warning::{Warning, WarningKind};
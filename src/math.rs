@@ -0,0 +1,86 @@
+//! Small vector helpers for geometry processing
+
+#[cfg(feature = "std")]
+pub fn sqrt(x: f32) -> f32 {
+    x.sqrt()
+}
+
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+pub fn sqrt(x: f32) -> f32 {
+    libm::sqrtf(x)
+}
+
+#[cfg(all(feature = "std", feature = "trimesh"))]
+pub fn floor(x: f32) -> f32 {
+    x.floor()
+}
+
+#[cfg(all(not(feature = "std"), feature = "libm", feature = "trimesh"))]
+pub fn floor(x: f32) -> f32 {
+    libm::floorf(x)
+}
+
+#[cfg(feature = "std")]
+pub fn acos(x: f32) -> f32 {
+    x.acos()
+}
+
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+pub fn acos(x: f32) -> f32 {
+    libm::acosf(x)
+}
+
+pub fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn length(a: [f32; 3]) -> f32 {
+    sqrt(dot(a, a))
+}
+
+/// Normalizes the vector, leaving zero-length vectors unchanged
+pub fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let len = length(a);
+    if len > 0.0 {
+        [a[0] / len, a[1] / len, a[2] / len]
+    } else {
+        a
+    }
+}
+
+/// Angle between two vectors in radians
+pub fn angle(a: [f32; 3], b: [f32; 3]) -> f32 {
+    acos(dot(normalize(a), normalize(b)).clamp(-1.0, 1.0))
+}
+
+/// Newell normal of a polygon, its length is twice the polygon area
+pub fn newell(points: impl Iterator<Item = [f32; 3]> + Clone) -> [f32; 3] {
+    let next = points.clone().cycle().skip(1);
+    points.zip(next).fold([0.0; 3], |n, (a, b)| {
+        [
+            n[0] + (a[1] - b[1]) * (a[2] + b[2]),
+            n[1] + (a[2] - b[2]) * (a[0] + b[0]),
+            n[2] + (a[0] - b[0]) * (a[1] + b[1]),
+        ]
+    })
+}
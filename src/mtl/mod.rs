@@ -1,47 +1,132 @@
 mod parser;
 
-use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
 
-use ahash::HashMap;
 use winnow::{BStr, Parser};
 
-use crate::WobjError;
+use crate::compat::*;
+use crate::util::Utf8;
+use crate::{ParseOptions, WobjError};
 
 /// Wavefront MTL data
+///
+/// Materials are kept in the order they are defined. If a material name is
+/// defined more than once, the last definition is kept at the position of the
+/// first one. With [`ParseOptions::strict`] duplicate names are an error instead.
 #[derive(Debug, Clone)]
-pub struct Mtl(HashMap<String, Material>);
+pub struct Mtl {
+    materials: Map<String, Material>,
+    #[cfg(feature = "std")]
+    base_dir: Option<PathBuf>,
+}
 
 impl Mtl {
     /// Parses MTL file data
+    ///
+    /// Malformed or truncated data is reported as an error and never panics.
     pub fn parse(bytes: &[u8]) -> Result<Self, WobjError> {
-        parser::parse_mtl
+        Self::parse_with(bytes, ParseOptions::default())
+    }
+
+    /// Parses MTL text
+    ///
+    /// Names and paths can not be invalid UTF-8, so they are not validated
+    /// and [`ParseOptions::on_invalid_utf8`] never applies.
+    ///
+    /// ```
+    /// let mtl = wobj::Mtl::parse_str(include_str!("../../assets/cube.mtl"))?;
+    /// assert!(mtl.get("CubeMaterial").is_some());
+    /// # Ok::<(), wobj::WobjError>(())
+    /// ```
+    pub fn parse_str(text: &str) -> Result<Self, WobjError> {
+        Self::parse_inner(text.as_bytes(), &ParseOptions::default(), Utf8::Valid)
+    }
+
+    /// Parses MTL file data with the specified options
+    pub fn parse_with(bytes: &[u8], options: ParseOptions) -> Result<Self, WobjError> {
+        Self::parse_inner(bytes, &options, Utf8::Checked(options.on_invalid_utf8))
+    }
+
+    fn parse_inner(bytes: &[u8], options: &ParseOptions, utf8: Utf8) -> Result<Self, WobjError> {
+        (|input: &mut &BStr| parser::parse_mtl(input, options, utf8))
             .parse(BStr::new(bytes))
             .map_err(WobjError::from)
             .map(Self::new)
     }
 
-    fn new(materials: HashMap<String, Material>) -> Self {
-        Self(materials)
+    #[cfg(feature = "std")]
+    /// Reads and parses an MTL file
+    ///
+    /// The directory of the file is remembered for resolving texture paths.
+    pub fn parse_path<P: AsRef<Path>>(path: P) -> Result<Self, WobjError> {
+        let path = path.as_ref();
+        let mut mtl = Self::parse(&std::fs::read(path)?)?;
+        mtl.base_dir = path.parent().map(Path::to_path_buf);
+        Ok(mtl)
+    }
+
+    fn new(materials: Map<String, Material>) -> Self {
+        Self {
+            materials,
+            #[cfg(feature = "std")]
+            base_dir: None,
+        }
+    }
+
+    /// Number of materials
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    /// Whether there are no materials
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
     }
 
     /// Gets the material with the specified name
     pub fn get(&self, name: &str) -> Option<&Material> {
-        self.0.get(name)
+        self.materials.get(name)
     }
 
-    /// Gets the underlying HashMap
-    pub fn inner(&self) -> &HashMap<String, Material> {
-        &self.0
+    #[cfg(feature = "std")]
+    /// Directory of the MTL file, if it was parsed with [`Mtl::parse_path`]
+    pub fn base_dir(&self) -> Option<&Path> {
+        self.base_dir.as_deref()
     }
 
-    /// Takes the underlying HashMap
-    pub fn into_inner(self) -> HashMap<String, Material> {
-        self.0
+    #[cfg(feature = "std")]
+    /// Path to the texture file relative to the MTL file
+    ///
+    /// Returns the path as written if the directory of the MTL file is unknown.
+    pub fn texture_path(&self, map: &TextureMap) -> PathBuf {
+        match &self.base_dir {
+            Some(base) => map.resolved_path(base),
+            None => PathBuf::from(map.path()),
+        }
+    }
+
+    /// Materials with their names in file order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Material)> {
+        self.materials
+            .iter()
+            .map(|(name, material)| (name.as_str(), material))
+    }
+
+    /// Gets the underlying map, in file order
+    pub fn inner(&self) -> &Map<String, Material> {
+        &self.materials
+    }
+
+    /// Takes the underlying map, in file order
+    pub fn into_inner(self) -> Map<String, Material> {
+        self.materials
     }
 }
 
 /// Wavefront MTL material data
 #[derive(Debug, Default, Clone)]
+#[non_exhaustive]
 pub struct Material {
     /// (Ka) ambient reflectivity
     pub ambient: Option<ColorValue>,
@@ -113,6 +198,300 @@ pub struct Material {
     pub emissive_map: Option<TextureMap>,
     /// (norm) normal texture
     pub normal_map: Option<TextureMap>,
+
+    /// Unknown statements as keyword and value, kept as written
+    ///
+    /// Values which are not valid UTF-8 are decoded lossily.
+    pub extra: Map<String, String>,
+}
+
+impl Material {
+    /// Builder for constructing a material in code
+    pub fn builder() -> MaterialBuilder {
+        MaterialBuilder::default()
+    }
+
+    /// Typed illumination model
+    pub fn illum_model(&self) -> Option<IlluminationModel> {
+        self.illum.map(IlluminationModel::from)
+    }
+
+    /// Linear RGB emission, the multiplier of the `map_Ke` texture if any
+    ///
+    /// Values above 1 are kept for HDR emission. Without `Ke` this is white
+    /// when there is an emissive texture and black otherwise.
+    pub fn emissive_factor(&self) -> [f32; 3] {
+        match &self.emissive {
+            Some(color) => color.to_rgb(),
+            None if self.emissive_map.is_some() => [1.0; 3],
+            None => [0.0; 3],
+        }
+    }
+}
+
+/// Builder for [`Material`]
+///
+/// Unset properties keep their default value.
+#[derive(Debug, Default, Clone)]
+pub struct MaterialBuilder(Material);
+
+impl MaterialBuilder {
+    /// (Ka) ambient reflectivity
+    pub fn ambient(mut self, ambient: ColorValue) -> Self {
+        self.0.ambient = Some(ambient);
+        self
+    }
+
+    /// (Kd) diffuse reflectivity
+    pub fn diffuse(mut self, diffuse: ColorValue) -> Self {
+        self.0.diffuse = Some(diffuse);
+        self
+    }
+
+    /// (Ks) specular reflectivity
+    pub fn specular(mut self, specular: ColorValue) -> Self {
+        self.0.specular = Some(specular);
+        self
+    }
+
+    /// (Tf) transmission filter
+    pub fn filter(mut self, filter: ColorValue) -> Self {
+        self.0.filter = Some(filter);
+        self
+    }
+
+    /// (illum) illumination model
+    pub fn illum(mut self, illum: u8) -> Self {
+        self.0.illum = Some(illum);
+        self
+    }
+
+    /// (d/Tr) dissolve factor
+    pub fn dissolve(mut self, dissolve: f32) -> Self {
+        self.0.dissolve = Some(dissolve);
+        self
+    }
+
+    /// (d -halo) dissolve halo
+    pub fn halo(mut self, halo: bool) -> Self {
+        self.0.halo = halo;
+        self
+    }
+
+    /// (Ns) specular exponent
+    pub fn exponent(mut self, exponent: f32) -> Self {
+        self.0.exponent = Some(exponent);
+        self
+    }
+
+    /// (sharpness) reflection sharpness
+    pub fn sharpness(mut self, sharpness: f32) -> Self {
+        self.0.sharpness = Some(sharpness);
+        self
+    }
+
+    /// (Ni) optical density
+    pub fn density(mut self, density: f32) -> Self {
+        self.0.density = Some(density);
+        self
+    }
+
+    /// (map_Ka) ambient texture
+    pub fn ambient_map(mut self, ambient_map: TextureMap) -> Self {
+        self.0.ambient_map = Some(ambient_map);
+        self
+    }
+
+    /// (map_Kd) diffuse texture
+    pub fn diffuse_map(mut self, diffuse_map: TextureMap) -> Self {
+        self.0.diffuse_map = Some(diffuse_map);
+        self
+    }
+
+    /// (map_Ks) specular texture
+    pub fn specular_map(mut self, specular_map: TextureMap) -> Self {
+        self.0.specular_map = Some(specular_map);
+        self
+    }
+
+    /// (map_Ns) specular exponent texture
+    pub fn exponent_map(mut self, exponent_map: TextureMap) -> Self {
+        self.0.exponent_map = Some(exponent_map);
+        self
+    }
+
+    /// (map_d) dissolve texture
+    pub fn dissolve_map(mut self, dissolve_map: TextureMap) -> Self {
+        self.0.dissolve_map = Some(dissolve_map);
+        self
+    }
+
+    /// (decal) decal texture
+    pub fn decal_map(mut self, decal_map: TextureMap) -> Self {
+        self.0.decal_map = Some(decal_map);
+        self
+    }
+
+    /// (disp) displacement texture
+    pub fn disp_map(mut self, disp_map: TextureMap) -> Self {
+        self.0.disp_map = Some(disp_map);
+        self
+    }
+
+    /// (bump/map_bump) bump texture
+    pub fn bump_map(mut self, bump_map: TextureMap) -> Self {
+        self.0.bump_map = Some(bump_map);
+        self
+    }
+
+    /// (map_aat) texture anti-aliasing
+    pub fn anti_aliasing(mut self, anti_aliasing: bool) -> Self {
+        self.0.anti_aliasing = anti_aliasing;
+        self
+    }
+
+    /// (refl) reflection map (type, map)
+    pub fn reflection(mut self, reflection: Refl) -> Self {
+        self.0.reflection = Some(reflection);
+        self
+    }
+
+    /// (Pr) roughness
+    pub fn roughness(mut self, roughness: f32) -> Self {
+        self.0.roughness = Some(roughness);
+        self
+    }
+
+    /// (Pm) metallic
+    pub fn metallic(mut self, metallic: f32) -> Self {
+        self.0.metallic = Some(metallic);
+        self
+    }
+
+    /// (Ps) sheen
+    pub fn sheen(mut self, sheen: f32) -> Self {
+        self.0.sheen = Some(sheen);
+        self
+    }
+
+    /// (Pc) clearcoat thickness
+    pub fn cc_thickness(mut self, cc_thickness: f32) -> Self {
+        self.0.cc_thickness = Some(cc_thickness);
+        self
+    }
+
+    /// (Pcr) clearcoat roughness
+    pub fn cc_roughness(mut self, cc_roughness: f32) -> Self {
+        self.0.cc_roughness = Some(cc_roughness);
+        self
+    }
+
+    /// (Ke) emissive
+    pub fn emissive(mut self, emissive: ColorValue) -> Self {
+        self.0.emissive = Some(emissive);
+        self
+    }
+
+    /// (aniso) anisotropy
+    pub fn anisotropy(mut self, anisotropy: f32) -> Self {
+        self.0.anisotropy = Some(anisotropy);
+        self
+    }
+
+    /// (anisor) anisotropy rotation
+    pub fn anisotropy_rotation(mut self, anisotropy_rotation: f32) -> Self {
+        self.0.anisotropy_rotation = Some(anisotropy_rotation);
+        self
+    }
+
+    /// (map_Pr) roughness texture
+    pub fn roughness_map(mut self, roughness_map: TextureMap) -> Self {
+        self.0.roughness_map = Some(roughness_map);
+        self
+    }
+
+    /// (map_Pm) metallic texture
+    pub fn metallic_map(mut self, metallic_map: TextureMap) -> Self {
+        self.0.metallic_map = Some(metallic_map);
+        self
+    }
+
+    /// (map_Ps) sheen texture
+    pub fn sheen_map(mut self, sheen_map: TextureMap) -> Self {
+        self.0.sheen_map = Some(sheen_map);
+        self
+    }
+
+    /// (map_Ke) emissive texture
+    pub fn emissive_map(mut self, emissive_map: TextureMap) -> Self {
+        self.0.emissive_map = Some(emissive_map);
+        self
+    }
+
+    /// (norm) normal texture
+    pub fn normal_map(mut self, normal_map: TextureMap) -> Self {
+        self.0.normal_map = Some(normal_map);
+        self
+    }
+
+    /// Adds an unknown statement
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.0.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Creates the material
+    pub fn build(self) -> Material {
+        self.0
+    }
+}
+
+/// Illumination model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IlluminationModel {
+    /// (0) color on and ambient off
+    Color,
+    /// (1) color on and ambient on
+    ColorAmbient,
+    /// (2) highlight on
+    Highlight,
+    /// (3) reflection on and ray trace on
+    ReflectionRaytrace,
+    /// (4) glass on, ray trace on
+    GlassRaytrace,
+    /// (5) fresnel on and ray trace on
+    FresnelRaytrace,
+    /// (6) refraction on, fresnel off and ray trace on
+    RefractionRaytrace,
+    /// (7) refraction on, fresnel on and ray trace on
+    RefractionFresnelRaytrace,
+    /// (8) reflection on and ray trace off
+    Reflection,
+    /// (9) glass on, ray trace off
+    Glass,
+    /// (10) casts shadows onto invisible surfaces
+    ShadowMatte,
+    /// Model not defined by the spec
+    Unknown(u8),
+}
+
+impl From<u8> for IlluminationModel {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Color,
+            1 => Self::ColorAmbient,
+            2 => Self::Highlight,
+            3 => Self::ReflectionRaytrace,
+            4 => Self::GlassRaytrace,
+            5 => Self::FresnelRaytrace,
+            6 => Self::RefractionRaytrace,
+            7 => Self::RefractionFresnelRaytrace,
+            8 => Self::Reflection,
+            9 => Self::Glass,
+            10 => Self::ShadowMatte,
+            other => Self::Unknown(other),
+        }
+    }
 }
 
 /// Color value
@@ -123,7 +502,7 @@ pub enum ColorValue {
     /// CIEXYZ values
     XYZ(f32, f32, f32),
     /// Spectral curve via .rfl file
-    Spectral { file: Box<PathBuf>, factor: f32 },
+    Spectral { file: String, factor: f32 },
 }
 
 impl ColorValue {
@@ -134,29 +513,131 @@ impl ColorValue {
     fn xyz(v: (f32, f32, f32)) -> Self {
         Self::XYZ(v.0, v.1, v.2)
     }
+
+    /// Linear RGB value of the color
+    ///
+    /// XYZ values are converted to linear sRGB primaries (D65). Spectral
+    /// curves are not read, so they become white scaled by the factor.
+    pub fn to_rgb(&self) -> [f32; 3] {
+        match *self {
+            Self::RGB(r, g, b) => [r, g, b],
+            Self::XYZ(x, y, z) => [
+                3.240_97 * x - 1.537_383 * y - 0.498_611 * z,
+                -0.969_244 * x + 1.875_968 * y + 0.041_555 * z,
+                0.055_630 * x - 0.203_977 * y + 1.056_972 * z,
+            ],
+            Self::Spectral { factor, .. } => [factor; 3],
+        }
+    }
 }
 
 /// Texture map
 #[derive(Debug, Clone)]
-pub struct TextureMap(Box<(PathBuf, Vec<MapOption>)>);
+pub struct TextureMap(Box<(String, Vec<MapOption>)>);
 
 impl TextureMap {
-    fn new(path: PathBuf, options: Vec<MapOption>) -> Self {
-        Self(Box::new((path, options)))
+    /// Creates a texture map from a path and modifier options
+    pub fn new(path: impl Into<String>, options: Vec<MapOption>) -> Self {
+        Self(Box::new((path.into(), options)))
     }
 
     /// Path to the texture file
-    pub fn path(&self) -> &PathBuf {
+    ///
+    /// Not exactly as written: separators are normalized to `/` and a
+    /// `file://` URI is reduced to its path.
+    pub fn path(&self) -> &str {
         &self.0.0
     }
 
+    #[cfg(feature = "std")]
+    /// Path to the texture file resolved against the directory of the MTL file
+    pub fn resolved_path(&self, base: &Path) -> PathBuf {
+        base.join(self.path())
+    }
+
     /// Modifier options
     pub fn options(&self) -> &[MapOption] {
         &self.0.1
     }
 
+    /// Bump multiplier (`-bm`), if set
+    pub fn bump_multiplier(&self) -> Option<f32> {
+        self.find_option(|o| match o {
+            MapOption::BumpMultiplier(bm) => Some(*bm),
+            _ => None,
+        })
+    }
+
+    /// UV offset (`-o`), if set
+    pub fn offset(&self) -> Option<[f32; 3]> {
+        self.find_option(|o| match o {
+            MapOption::Offset(u, v, w) => Some([*u, *v, *w]),
+            _ => None,
+        })
+    }
+
+    /// UV scale (`-s`), `[1, 1, 1]` if not set
+    pub fn scale(&self) -> [f32; 3] {
+        self.find_option(|o| match o {
+            MapOption::Scale(u, v, w) => Some([*u, *v, *w]),
+            _ => None,
+        })
+        .unwrap_or([1.0; 3])
+    }
+
+    /// Channel to sample (`-imfchan`), with the spec default for the kind of map if not set
+    ///
+    /// Returns `None` for color maps without the option, which use all channels.
+    pub fn channel(&self, kind: MapKind) -> Option<Channel> {
+        let channel = self.find_option(|o| match o {
+            MapOption::Channel(channel) => Some(*channel),
+            _ => None,
+        });
+        channel.or(match kind {
+            MapKind::Color => None,
+            MapKind::Scalar | MapKind::Bump => Some(Channel::Luminance),
+            MapKind::Decal => Some(Channel::Matte),
+        })
+    }
+
+    /// UV clamping (`-clamp`), off if not set
+    pub fn clamp(&self) -> bool {
+        self.find_option(|o| match o {
+            MapOption::Clamp(clamp) => Some(*clamp),
+            _ => None,
+        })
+        .unwrap_or(false)
+    }
+
+    /// All options, with the MTL spec defaults for the ones not set
+    pub fn resolved(&self) -> ResolvedMapOptions {
+        let mut resolved = ResolvedMapOptions::default();
+        for option in self.options() {
+            match *option {
+                MapOption::BlendU(on) => resolved.blend_u = on,
+                MapOption::BlendV(on) => resolved.blend_v = on,
+                MapOption::BumpMultiplier(bm) => resolved.bump_multiplier = bm,
+                MapOption::Boost(boost) => resolved.boost = boost,
+                MapOption::ColorCorrection(on) => resolved.color_correction = on,
+                MapOption::Clamp(on) => resolved.clamp = on,
+                MapOption::Channel(channel) => resolved.channel = Some(channel),
+                MapOption::MM(base, gain) => (resolved.base, resolved.gain) = (base, gain),
+                MapOption::Offset(u, v, w) => resolved.offset = [u, v, w],
+                MapOption::Scale(u, v, w) => resolved.scale = [u, v, w],
+                MapOption::Turbulence(u, v, w) => resolved.turbulence = [u, v, w],
+                MapOption::Resolution(res) => resolved.resolution = Some(res),
+            }
+        }
+        resolved
+    }
+
+    /// Value of the last matching option, as later options override earlier ones
+    fn find_option<T>(&self, f: impl FnMut(&MapOption) -> Option<T>) -> Option<T> {
+        self.options().iter().rev().find_map(f)
+    }
+
     /// Takes the underlying data
-    pub fn take(self) -> (PathBuf, Vec<MapOption>) {
+    pub fn take(self) -> (String, Vec<MapOption>) {
         *self.0
     }
 }
@@ -190,8 +671,62 @@ pub enum MapOption {
     Resolution(u16),
 }
 
+/// Texture map options with defaults filled in, see [`TextureMap::resolved`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedMapOptions {
+    /// (blendu) horizontal blending, on by default
+    pub blend_u: bool,
+    /// (blendv) vertical blending, on by default
+    pub blend_v: bool,
+    /// (bm) bump multiplier, 1 by default
+    pub bump_multiplier: f32,
+    /// (boost) mip-mapped clarity boost, 0 by default
+    pub boost: f32,
+    /// (cc) color correction, off by default
+    pub color_correction: bool,
+    /// (clamp) UV clamping, off by default
+    pub clamp: bool,
+    /// (imfchan) channel to use
+    ///
+    /// If not set, the spec defaults to luminance for bump and scalar maps
+    /// and to matte for decal maps, color maps use all channels.
+    pub channel: Option<Channel>,
+    /// (mm) base value, 0 by default
+    pub base: f32,
+    /// (mm) gain value, 1 by default
+    pub gain: f32,
+    /// (o) UV offset, 0 by default
+    pub offset: [f32; 3],
+    /// (s) UV scale, 1 by default
+    pub scale: [f32; 3],
+    /// (t) UV turbulence, 0 by default
+    pub turbulence: [f32; 3],
+    /// (texres) resolution, the texture size by default
+    pub resolution: Option<u16>,
+}
+
+impl Default for ResolvedMapOptions {
+    fn default() -> Self {
+        Self {
+            blend_u: true,
+            blend_v: true,
+            bump_multiplier: 1.0,
+            boost: 0.0,
+            color_correction: false,
+            clamp: false,
+            channel: None,
+            base: 0.0,
+            gain: 1.0,
+            offset: [0.0; 3],
+            scale: [1.0; 3],
+            turbulence: [0.0; 3],
+            resolution: None,
+        }
+    }
+}
+
 /// Texture map channel
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Channel {
     Red,
     Green,
@@ -201,9 +736,137 @@ pub enum Channel {
     ZDepth,
 }
 
+/// Kind of texture map, which decides its default channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapKind {
+    /// Color maps like `map_Kd` and `norm`
+    Color,
+    /// Single value maps like `map_Ns`, `map_d`, `disp` and `map_Pr`
+    Scalar,
+    /// Bump maps (`bump`)
+    Bump,
+    /// Decal maps (`decal`)
+    Decal,
+}
+
 /// Reflection map
+///
+/// Cube maps are keyed by side: `top`, `bottom`, `front`, `back`, `left` or
+/// `right`. Repeated statements for the same map replace the earlier one.
 #[derive(Debug, Clone)]
 pub enum Refl {
     Sphere(TextureMap),
-    Cube(HashMap<String, TextureMap>),
+    Cube(Map<String, TextureMap>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn material_builder() {
+        let material = Material::builder()
+            .diffuse(ColorValue::RGB(1.0, 0.5, 0.0))
+            .roughness(0.5)
+            .diffuse_map(TextureMap::new("diffuse.png", vec![MapOption::Clamp(true)]))
+            .halo(true)
+            .build();
+
+        assert!(matches!(
+            material.diffuse,
+            Some(ColorValue::RGB(1.0, 0.5, 0.0))
+        ));
+        assert_eq!(material.roughness, Some(0.5));
+        assert!(material.halo);
+        assert!(material.ambient.is_none());
+
+        let map = material.diffuse_map.unwrap();
+        assert_eq!(map.path(), "diffuse.png");
+        assert!(matches!(map.options(), [MapOption::Clamp(true)]));
+    }
+
+    #[test]
+    fn color_to_rgb() {
+        let close = |a: [f32; 3], b: [f32; 3]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3);
+        assert_eq!(ColorValue::RGB(0.2, 1.5, 0.0).to_rgb(), [0.2, 1.5, 0.0]);
+        // sRGB primaries
+        let red = ColorValue::XYZ(0.412_456, 0.212_673, 0.019_334);
+        assert!(close(red.to_rgb(), [1.0, 0.0, 0.0]));
+        let green = ColorValue::XYZ(0.357_576, 0.715_152, 0.119_192);
+        assert!(close(green.to_rgb(), [0.0, 1.0, 0.0]));
+        let blue = ColorValue::XYZ(0.180_437, 0.072_175, 0.950_304);
+        assert!(close(blue.to_rgb(), [0.0, 0.0, 1.0]));
+
+        let spectral = ColorValue::Spectral {
+            file: "a.rfl".into(),
+            factor: 0.5,
+        };
+        assert_eq!(spectral.to_rgb(), [0.5; 3]);
+    }
+
+    #[test]
+    fn emissive_factor() {
+        let mtl = Mtl::parse(
+            b"newmtl Hdr\nKe 4 2 0.5\nmap_Ke glow.png\n\
+            newmtl Xyz\nKe xyz 0.950456 1 1.088754\n\
+            newmtl Spectral\nKe spectral lamp.rfl 3\n\
+            newmtl Map\nmap_Ke glow.png\nnewmtl None\n",
+        )
+        .unwrap();
+        let factor = |name| mtl.get(name).unwrap().emissive_factor();
+        assert_eq!(factor("Hdr"), [4.0, 2.0, 0.5]);
+        // D65 white point
+        assert!(factor("Xyz").iter().all(|c| (c - 1.0).abs() < 1e-3));
+        assert_eq!(factor("Spectral"), [3.0; 3]);
+        assert_eq!(factor("Map"), [1.0; 3]);
+        assert_eq!(factor("None"), [0.0; 3]);
+    }
+
+    #[test]
+    fn resolved_map_options() {
+        let mtl = Mtl::parse(b"newmtl A\nbump -bm 0.3 a.png\n").unwrap();
+        let map = mtl.get("A").unwrap().bump_map.as_ref().unwrap();
+        let expected = ResolvedMapOptions {
+            bump_multiplier: 0.3,
+            ..Default::default()
+        };
+        assert_eq!(map.resolved(), expected);
+        assert!(expected.blend_u && expected.blend_v && !expected.clamp);
+        assert_eq!((expected.base, expected.gain), (0.0, 1.0));
+        assert_eq!(expected.scale, [1.0; 3]);
+    }
+
+    #[test]
+    fn map_option_accessors() {
+        let map = TextureMap::new("a.png", vec![]);
+        assert_eq!(map.bump_multiplier(), None);
+        assert_eq!(map.offset(), None);
+        assert_eq!(map.scale(), [1.0; 3]);
+        assert!(!map.clamp());
+
+        let mtl = Mtl::parse(b"newmtl A\nbump -bm 0.5 -s 2 3 1 -o 0.1 0 0 -clamp on -bm 2 a.png\n");
+        let mtl = mtl.unwrap();
+        let map = mtl.get("A").unwrap().bump_map.as_ref().unwrap();
+        assert_eq!(map.bump_multiplier(), Some(2.0));
+        assert_eq!(map.offset(), Some([0.1, 0.0, 0.0]));
+        assert_eq!(map.scale(), [2.0, 3.0, 1.0]);
+        assert!(map.clamp());
+    }
+
+    #[test]
+    fn map_channel() {
+        let mtl = Mtl::parse(b"newmtl A\nbump a.png\nmap_Kd b.png\ndecal c.png\n").unwrap();
+        let material = mtl.get("A").unwrap();
+        let bump = material.bump_map.as_ref().unwrap();
+        assert_eq!(bump.channel(MapKind::Bump), Some(Channel::Luminance));
+        let diffuse = material.diffuse_map.as_ref().unwrap();
+        assert_eq!(diffuse.channel(MapKind::Color), None);
+        let decal = material.decal_map.as_ref().unwrap();
+        assert_eq!(decal.channel(MapKind::Decal), Some(Channel::Matte));
+
+        let mtl = Mtl::parse(b"newmtl A\nbump -imfchan g a.png\n").unwrap();
+        let bump = mtl.get("A").unwrap().bump_map.as_ref().unwrap();
+        assert_eq!(bump.channel(MapKind::Bump), Some(Channel::Green));
+    }
 }
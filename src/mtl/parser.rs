@@ -1,28 +1,54 @@
-use std::path::PathBuf;
-
-use ahash::HashMap;
-use winnow::ascii::{dec_uint, float, space1, till_line_ending};
+use winnow::ascii::{dec_uint, float, space1};
 use winnow::combinator::{
-    alt, delimited, dispatch, fail, opt, preceded, repeat, separated_pair, terminated,
+    alt, delimited, dispatch, eof, fail, opt, peek, preceded, repeat, separated_pair, terminated,
 };
 use winnow::error::{ContextError, FromExternalError};
 use winnow::{BStr, Result, prelude::*};
 
 use super::{Channel, ColorValue, MapOption, Material, Refl, TextureMap};
-use crate::util::{expected, ignoreable, label, parse_path, to_next_line, word};
+use crate::compat::*;
+use crate::util::{
+    Utf8, decode, expected, ignoreable, label, line_end, parse_path, skip_bom, till_line_end,
+    to_next_line, unknown_keyword, word,
+};
+use crate::{ParseOptions, WobjError};
 
-pub(crate) fn parse_mtl(input: &mut &BStr) -> Result<HashMap<String, Material>> {
-    let mut materials = HashMap::default();
+pub(crate) fn parse_mtl(
+    input: &mut &BStr,
+    options: &ParseOptions,
+    utf8: Utf8,
+) -> Result<Map<String, Material>> {
+    skip_bom(input)?;
+    let mut materials = Map::default();
+
+    loop {
+        ignoreable.parse_next(input)?;
+        let statement = *input;
+        let Ok(name) = parse_name(utf8).parse_next(input) else {
+            break;
+        };
+        if options.strict
+            && let Some(name) = &name
+            && materials.contains_key(name)
+        {
+            *input = statement;
+            let message = format!("duplicate material '{name}'");
+            return Err(ContextError::from_external_error(
+                input,
+                WobjError::from(message),
+            ));
+        }
 
-    while let Ok(name) = parse_name(input) {
-        let material = parse_material(input)?;
-        materials.insert(name, material);
+        let material = parse_material(input, options, utf8)?;
+        if let Some(name) = name {
+            materials.insert(name, material);
+        }
     }
 
     Ok(materials)
 }
 
-fn parse_material(input: &mut &BStr) -> Result<Material> {
+fn parse_material(input: &mut &BStr, options: &ParseOptions, utf8: Utf8) -> Result<Material> {
     let mut material = Material::default();
 
     while let Ok(key) = keyword(input) {
@@ -88,86 +114,73 @@ fn parse_material(input: &mut &BStr) -> Result<Material> {
                 )
             }
             b"map_ka" => {
-                material.ambient_map = Some(
-                    parse_map
-                        .context(label("ambient texture (map_Ka)"))
-                        .parse_next(input)?,
-                )
+                material.ambient_map = parse_map(utf8)
+                    .context(label("ambient texture (map_Ka)"))
+                    .parse_next(input)?
             }
             b"map_kd" => {
-                material.diffuse_map = Some(
-                    parse_map
-                        .context(label("diffuse texture (map_Kd)"))
-                        .parse_next(input)?,
-                )
+                material.diffuse_map = parse_map(utf8)
+                    .context(label("diffuse texture (map_Kd)"))
+                    .parse_next(input)?
             }
             b"map_ks" => {
-                material.specular_map = Some(
-                    parse_map
-                        .context(label("specular texture (map_Ks)"))
-                        .parse_next(input)?,
-                )
+                material.specular_map = parse_map(utf8)
+                    .context(label("specular texture (map_Ks)"))
+                    .parse_next(input)?
             }
             b"map_ns" => {
-                material.exponent_map = Some(
-                    parse_map
-                        .context(label("specular exponent texture (map_Ns)"))
-                        .parse_next(input)?,
-                )
+                material.exponent_map = parse_map(utf8)
+                    .context(label("specular exponent texture (map_Ns)"))
+                    .parse_next(input)?
             }
             b"map_d" => {
-                material.dissolve_map = Some(
-                    parse_map
-                        .context(label("dissolve texture (map_d)"))
-                        .parse_next(input)?,
-                )
+                material.dissolve_map = parse_map(utf8)
+                    .context(label("dissolve texture (map_d)"))
+                    .parse_next(input)?
             }
             b"decal" => {
-                material.decal_map = Some(
-                    parse_map
-                        .context(label("decal texture (decal)"))
-                        .parse_next(input)?,
-                )
+                material.decal_map = parse_map(utf8)
+                    .context(label("decal texture (decal)"))
+                    .parse_next(input)?
             }
             b"disp" => {
-                material.disp_map = Some(
-                    parse_map
-                        .context(label("displacement texture (disp)"))
-                        .parse_next(input)?,
-                )
+                material.disp_map = parse_map(utf8)
+                    .context(label("displacement texture (disp)"))
+                    .parse_next(input)?
             }
             b"bump" | b"map_bump" => {
-                material.bump_map = Some(
-                    parse_map
-                        .context(label("bump texture (bump/map_bump)"))
-                        .parse_next(input)?,
-                )
+                material.bump_map = parse_map(utf8)
+                    .context(label("bump texture (bump/map_bump)"))
+                    .parse_next(input)?
             }
             b"map_aat" => {
-                material.anti_aliasing = parse_on_off
+                // A bare 'map_aat' turns anti-aliasing on
+                material.anti_aliasing = opt(parse_on_off)
+                    .map(|v| v.unwrap_or(true))
                     .context(label("anti-aliasing (map_aat)"))
                     .parse_next(input)?
             }
             b"refl" => {
-                let (shape, map) = parse_relf
+                let (side, map) = parse_relf(utf8)
                     .context(label("reflection map (refl)"))
                     .parse_next(input)?;
 
-                match shape {
-                    b"sphere" => {
-                        material.reflection = Some(Refl::Sphere(map));
+                let mixed = || {
+                    let message = "refl mixes sphere and cube maps";
+                    ContextError::from_external_error(input, WobjError::from(message))
+                };
+                match (side, map, &mut material.reflection) {
+                    (_, None, _) => (),
+                    (None, Some(_), Some(Refl::Cube(_))) => return Err(mixed()),
+                    (None, Some(map), reflection) => *reflection = Some(Refl::Sphere(map)),
+                    (Some(_), Some(_), Some(Refl::Sphere(_))) => return Err(mixed()),
+                    (Some(side), Some(map), Some(Refl::Cube(sides))) => {
+                        sides.insert(side.to_string(), map);
                     }
-                    cube_side => {
-                        let side = String::from_utf8(cube_side.to_vec())
-                            .map_err(|e| ContextError::from_external_error(input, e))?;
-
-                        if let Some(Refl::Cube(sides)) = &mut material.reflection {
-                            sides.insert(side, map);
-                        } else {
-                            let mut hashmap = HashMap::default();
-                            hashmap.insert(side, map);
-                            material.reflection = Some(Refl::Cube(hashmap))
-                        }
+                    (Some(side), Some(map), reflection @ None) => {
+                        let mut sides = Map::default();
+                        sides.insert(side.to_string(), map);
+                        *reflection = Some(Refl::Cube(sides));
                     }
                 }
             }
@@ -229,41 +242,39 @@ fn parse_material(input: &mut &BStr) -> Result<Material> {
                 )
             }
             b"map_pr" => {
-                material.roughness_map = Some(
-                    parse_map
-                        .context(label("roughness texture (map_Pr)"))
-                        .parse_next(input)?,
-                )
+                material.roughness_map = parse_map(utf8)
+                    .context(label("roughness texture (map_Pr)"))
+                    .parse_next(input)?
             }
             b"map_pm" => {
-                material.metallic_map = Some(
-                    parse_map
-                        .context(label("metallic texture (map_Pm)"))
-                        .parse_next(input)?,
-                )
+                material.metallic_map = parse_map(utf8)
+                    .context(label("metallic texture (map_Pm)"))
+                    .parse_next(input)?
             }
             b"map_ps" => {
-                material.sheen_map = Some(
-                    parse_map
-                        .context(label("sheen texture (map_Ps)"))
-                        .parse_next(input)?,
-                )
+                material.sheen_map = parse_map(utf8)
+                    .context(label("sheen texture (map_Ps)"))
+                    .parse_next(input)?
             }
             b"map_ke" => {
-                material.emissive_map = Some(
-                    parse_map
-                        .context(label("emissive texture (map_Ke)"))
-                        .parse_next(input)?,
-                )
+                material.emissive_map = parse_map(utf8)
+                    .context(label("emissive texture (map_Ke)"))
+                    .parse_next(input)?
             }
             b"norm" => {
-                material.normal_map = Some(
-                    parse_map
-                        .context(label("normal texture (norm)"))
-                        .parse_next(input)?,
-                )
+                material.normal_map = parse_map(utf8)
+                    .context(label("normal texture (norm)"))
+                    .parse_next(input)?
+            }
+            _ if options.strict => return Err(unknown_keyword(input, key)),
+            _ => {
+                // Keep unknown statements for custom shaders and round-tripping
+                let value = till_line_end.map(<[u8]>::trim_ascii).parse_next(input)?;
+                material.extra.insert(
+                    String::from_utf8_lossy(key).into_owned(),
+                    String::from_utf8_lossy(value).into_owned(),
+                );
             }
-            _ => (),
         }
 
         to_next_line(input)?;
@@ -272,16 +283,16 @@ fn parse_material(input: &mut &BStr) -> Result<Material> {
     Ok(material)
 }
 
-fn parse_name(input: &mut &BStr) -> Result<String> {
+fn parse_name<'a>(utf8: Utf8) -> impl Parser<&'a BStr, Option<String>, ContextError> {
     delimited(ignoreable, preceded("newmtl ", word), to_next_line)
-        .try_map(|s| String::from_utf8(s.to_vec()))
+        .try_map(move |s| decode(s, utf8).map(|name| name.map(Cow::into_owned)))
         .context(label("Material name statement"))
         .context(expected("newmtl <name>"))
-        .parse_next(input)
 }
 
 fn keyword<'a>(input: &mut &'a BStr) -> Result<&'a [u8]> {
-    delimited(ignoreable, word, space1)
+    // The value may be missing for keywords at the end of the line
+    delimited(ignoreable, word, alt((space1, peek(line_end), eof)))
         .verify(|k: &[_]| k != b"newmtl")
         .context(label("keyword"))
         .parse_next(input)
@@ -313,19 +324,21 @@ fn parse_spectral(input: &mut &BStr) -> Result<ColorValue> {
         // With factor
         separated_pair(word, space1, float),
         // Without factor
-        till_line_ending.map(|file| (file, 1.0)),
+        till_line_end.map(|file| (file, 1.0)),
     ))
     // Convert file str to path
-    .try_map(|(file, factor)| str::from_utf8(file).map(|s| (Box::new(PathBuf::from(s)), factor)))
+    .try_map(|(file, factor)| str::from_utf8(file).map(|s| (s.to_string(), factor)))
     .parse_next(input)?;
 
     Ok(ColorValue::Spectral { file, factor })
 }
 
-fn parse_map(input: &mut &BStr) -> Result<TextureMap> {
-    let options = repeat(0.., terminated(parse_map_option, space1)).parse_next(input)?;
-    let path = parse_path.parse_next(input)?;
-    Ok(TextureMap::new(path, options))
+fn parse_map<'a>(utf8: Utf8) -> impl Parser<&'a BStr, Option<TextureMap>, ContextError> {
+    (
+        repeat(0.., terminated(parse_map_option, space1)),
+        parse_path(utf8),
+    )
+        .map(|(options, path)| Some(TextureMap::new(path?, options)))
 }
 
 fn parse_map_option(input: &mut &BStr) -> Result<MapOption> {
@@ -390,26 +403,304 @@ fn parse_uv_turbulance(input: &mut &BStr) -> Result<MapOption> {
         .parse_next(input)
 }
 
-fn parse_relf<'a>(input: &mut &'a BStr) -> Result<(&'a [u8], TextureMap)> {
-    let shape = alt((
-        delimited("-type ", "sphere", space1),
-        delimited("-type cube_", word, space1),
-    ))
-    .parse_next(input)?;
+/// Parses a reflection map with its cube side, or `None` for a sphere map
+fn parse_relf<'a>(
+    utf8: Utf8,
+) -> impl Parser<&'a BStr, (Option<&'static str>, Option<TextureMap>), ContextError> {
+    (
+        alt((
+            delimited("-type ", "sphere", space1).value(None),
+            delimited("-type cube_", parse_cube_side, space1).map(Some),
+        )),
+        parse_map(utf8),
+    )
+}
 
-    let map = parse_map.parse_next(input)?;
-    Ok((shape, map))
+fn parse_cube_side(input: &mut &BStr) -> Result<&'static str> {
+    const SIDES: [&str; 6] = ["top", "bottom", "front", "back", "left", "right"];
+    word.try_map(
+        |side: &[u8]| match SIDES.iter().find(|s| s.as_bytes() == side) {
+            Some(side) => Ok(*side),
+            None => Err(WobjError::from(format!(
+                "unknown cube side '{}'",
+                String::from_utf8_lossy(side)
+            ))),
+        },
+    )
+    .parse_next(input)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{IlluminationModel, InvalidUtf8, Mtl};
 
     #[test]
     fn name_parsing() {
-        assert_eq!(parse_name(&mut BStr::new("newmtl Mat")).unwrap(), "Mat");
-        assert_eq!(parse_name(&mut BStr::new("\nnewmtl Mat")).unwrap(), "Mat");
-        assert_eq!(parse_name(&mut BStr::new("#C\nnewmtl Mat")).unwrap(), "Mat");
-        assert!(parse_name(&mut BStr::new("invalid newmtl")).is_err())
+        assert_eq!(
+            parse_name(Utf8::Checked(InvalidUtf8::Error))
+                .parse_next(&mut BStr::new("newmtl Mat"))
+                .unwrap(),
+            Some("Mat".into())
+        );
+        assert_eq!(
+            parse_name(Utf8::Checked(InvalidUtf8::Error))
+                .parse_next(&mut BStr::new("\nnewmtl Mat"))
+                .unwrap(),
+            Some("Mat".into())
+        );
+        assert_eq!(
+            parse_name(Utf8::Checked(InvalidUtf8::Error))
+                .parse_next(&mut BStr::new("#C\nnewmtl Mat"))
+                .unwrap(),
+            Some("Mat".into())
+        );
+        assert!(
+            parse_name(Utf8::Checked(InvalidUtf8::Error))
+                .parse_next(&mut BStr::new("invalid newmtl"))
+                .is_err()
+        )
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        assert!(Mtl::parse(b"newmtl \xFF\n").is_err());
+
+        let bytes = b"newmtl \xFF\nKd 1 0 0\nnewmtl B\nmap_Kd \xFF.png\n";
+        let lossy = ParseOptions {
+            on_invalid_utf8: InvalidUtf8::Lossy,
+            ..Default::default()
+        };
+        let mtl = Mtl::parse_with(bytes, lossy).unwrap();
+        assert!(mtl.get("\u{FFFD}").is_some());
+        let map = mtl.get("B").unwrap().diffuse_map.as_ref().unwrap();
+        assert_eq!(map.path(), "\u{FFFD}.png");
+
+        let skip = ParseOptions {
+            on_invalid_utf8: InvalidUtf8::Skip,
+            ..Default::default()
+        };
+        let mtl = Mtl::parse_with(bytes, skip).unwrap();
+        assert_eq!(mtl.inner().len(), 1);
+        assert!(mtl.get("B").unwrap().diffuse_map.is_none());
+        assert!(Mtl::parse(b"newmtl A\nrefl -type cube_\xFF side.png\n").is_err());
+    }
+
+    #[test]
+    fn line_endings() {
+        for ending in ["\n", "\r\n", "\r"] {
+            let bytes = [
+                "newmtl A",
+                "Kd 1 0 0",
+                "map_Kd a.png",
+                "map_aat",
+                "newmtl B",
+                "Ns 10",
+            ]
+            .join(ending);
+            let mtl = Mtl::parse(bytes.as_bytes()).unwrap();
+            let a = mtl.get("A").unwrap();
+            assert_eq!(a.diffuse_map.as_ref().unwrap().path(), "a.png");
+            assert!(a.anti_aliasing);
+            assert!(mtl.get("B").is_some());
+        }
+    }
+
+    #[test]
+    fn keyword_without_value() {
+        assert_eq!(keyword(&mut BStr::new("map_aat\n")).unwrap(), b"map_aat");
+        assert_eq!(keyword(&mut BStr::new("map_aat")).unwrap(), b"map_aat");
+        assert_eq!(keyword(&mut BStr::new("map_aat on")).unwrap(), b"map_aat");
+
+        let options = ParseOptions::default();
+        let mut input = BStr::new("map_aat\nKd 1 1 1\n");
+        let material = parse_material(&mut input, &options, Utf8::Valid).unwrap();
+        assert!(material.anti_aliasing);
+        assert!(material.diffuse.is_some());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn texture_path_resolution() {
+        let dir = std::env::temp_dir().join(format!("wobj-mtl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.mtl");
+        std::fs::write(&path, "newmtl Mat\nmap_Kd textures/diffuse.png\n").unwrap();
+
+        let mtl = crate::Mtl::parse_path(&path).unwrap();
+        let map = mtl.get("Mat").unwrap().diffuse_map.as_ref().unwrap();
+        let expected = dir.join("textures").join("diffuse.png");
+        assert_eq!(mtl.base_dir(), Some(dir.as_path()));
+        assert_eq!(map.resolved_path(&dir), expected);
+        assert_eq!(mtl.texture_path(map), expected);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn windows_paths() {
+        let mtl = Mtl::parse(
+            b"newmtl A\nmap_Kd textures\\wood.png\nmap_Ks textures\\\\metal.png\n\
+            bump file:///C:/maps/bump.png\nmap_d file:///home/maps/alpha.png\n",
+        )
+        .unwrap();
+        let material = mtl.get("A").unwrap();
+        let path = |map: &Option<TextureMap>| map.as_ref().unwrap().path().to_owned();
+
+        assert_eq!(path(&material.diffuse_map), "textures/wood.png");
+        assert_eq!(path(&material.specular_map), "textures/metal.png");
+        assert_eq!(path(&material.bump_map), "C:/maps/bump.png");
+        assert_eq!(path(&material.dissolve_map), "/home/maps/alpha.png");
+
+        let base = std::path::Path::new("assets");
+        let wood = material.diffuse_map.as_ref().unwrap().resolved_path(base);
+        assert_eq!(wood, base.join("textures").join("wood.png"));
+    }
+
+    #[test]
+    fn error_context() {
+        let error = Mtl::parse(b"newmtl Mat\nKd red\n").unwrap_err();
+        assert_eq!(error.labels(), ["diffuse (Kd)"]);
+        assert_eq!(
+            error.expected(),
+            ["r g b", "spectral file.rfl factor", "xyz x y z"]
+        );
+    }
+
+    #[test]
+    fn truncated_input() {
+        let bytes = b"newmtl a\nKa 1 1 1\nKd spectral f.rfl 1\nKs xyz 1 1 1\nNs 10\n\
+            d -halo 0.5\nillum 2\nmap_Kd -o 1 2 3 -s 1 1 1 -clamp on -mm 0 1 t.png\n\
+            refl -type cube_top a.png\nbump -bm 1 b.png\nnewmtl b\n";
+        for i in 0..bytes.len() {
+            let mut damaged = bytes.to_vec();
+            damaged[i] = b'0';
+            let _ = Mtl::parse(&bytes[..i]);
+            let _ = Mtl::parse(&damaged);
+        }
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let mtl = Mtl::parse("\u{FEFF}newmtl Mat\nKd 1 0 0\n".as_bytes()).unwrap();
+        assert_eq!(
+            mtl.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["Mat"]
+        );
+    }
+
+    #[test]
+    fn illumination_model() {
+        let options = ParseOptions::default();
+        let parse =
+            |text: &str| parse_material(&mut BStr::new(text), &options, Utf8::Valid).unwrap();
+
+        assert_eq!(parse("Kd 1 1 1\n").illum_model(), None);
+        assert_eq!(
+            parse("illum 2\n").illum_model(),
+            Some(IlluminationModel::Highlight)
+        );
+        assert_eq!(
+            parse("illum 10\n").illum_model(),
+            Some(IlluminationModel::ShadowMatte)
+        );
+        assert_eq!(
+            parse("illum 42\n").illum_model(),
+            Some(IlluminationModel::Unknown(42))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn strict_parsing() {
+        let bytes = b"newmtl Mat\nKd 1 1 1\nKq 1\n";
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        assert!(crate::Mtl::parse(bytes).is_ok());
+        let error = crate::Mtl::parse_with(bytes, options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("line 3"));
+        assert!(error.contains("unknown keyword 'Kq'"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn cube_reflection() {
+        let sides = ["top", "bottom", "front", "back", "left", "right"];
+        let mut bytes = String::from("newmtl Mat\n");
+        for side in sides {
+            bytes += &format!("refl -type cube_{side} -clamp on {side}.png\n");
+        }
+        let mtl = Mtl::parse(bytes.as_bytes()).unwrap();
+        let Some(Refl::Cube(maps)) = &mtl.get("Mat").unwrap().reflection else {
+            panic!("expected cube reflection");
+        };
+        assert_eq!(maps.len(), 6);
+        for side in sides {
+            assert_eq!(maps[side].path(), format!("{side}.png"));
+        }
+
+        let error = Mtl::parse(b"newmtl Mat\nrefl -type cube_sideways a.png\n").unwrap_err();
+        assert!(error.to_string().contains("unknown cube side 'sideways'"));
+
+        let mixed = b"newmtl Mat\nrefl -type cube_top a.png\nrefl -type sphere b.png\n";
+        let error = Mtl::parse(mixed).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("refl mixes sphere and cube maps")
+        );
+
+        let mtl = Mtl::parse(b"newmtl Mat\nrefl -type sphere a.png\nrefl -type sphere b.png\n");
+        let material = mtl.unwrap().into_inner().swap_remove("Mat").unwrap();
+        assert!(
+            matches!(material.reflection, Some(Refl::Sphere(map)) if map.path().ends_with("b.png"))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn duplicate_materials() {
+        let bytes = b"newmtl Mat\nKd 1 0 0\nnewmtl Other\nnewmtl Mat\nKd 0 1 0\n";
+        let mtl = Mtl::parse(bytes).unwrap();
+        assert_eq!(mtl.len(), 2);
+        assert!(matches!(
+            mtl.get("Mat").unwrap().diffuse,
+            Some(ColorValue::RGB(0.0, 1.0, 0.0))
+        ));
+
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = Mtl::parse_with(bytes, options).unwrap_err().to_string();
+        assert!(error.contains("line 4"), "{error}");
+        assert!(error.contains("duplicate material 'Mat'"));
+    }
+
+    #[test]
+    fn material_order() {
+        let names = ["Zeta", "alpha", "Mid", "beta", "0"];
+        let bytes: String = names
+            .iter()
+            .map(|n| format!("newmtl {n}\nKd 1 1 1\n"))
+            .collect();
+        let mtl = Mtl::parse(bytes.as_bytes()).unwrap();
+        assert_eq!(mtl.iter().map(|(name, _)| name).collect::<Vec<_>>(), names);
+    }
+
+    #[test]
+    fn extra_statements() {
+        let mtl = crate::Mtl::parse(b"newmtl Mat\nPxx 0.3\nKd 1 1 1\nvendor_flag\n").unwrap();
+        let material = mtl.get("Mat").unwrap();
+        assert_eq!(material.extra.len(), 2);
+        assert_eq!(material.extra["Pxx"], "0.3");
+        assert_eq!(material.extra["vendor_flag"], "");
+        assert!(material.diffuse.is_some());
     }
 }
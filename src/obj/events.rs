@@ -0,0 +1,85 @@
+use core::num::NonZero;
+
+use winnow::{BStr, Parser};
+
+use super::{FacePoint, Obj, parser};
+use crate::WobjError;
+use crate::compat::*;
+
+/// Statement reported by [`Obj::parse_events`]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ObjEvent<'a> {
+    /// (v) vertex position
+    Vertex([f32; 3]),
+    /// (vn) vertex normal
+    Normal([f32; 3]),
+    /// (vt) texture coordinates
+    Uv([f32; 2]),
+    /// (f) face with zero-based indices into the data reported so far
+    Face(&'a [FacePoint<usize>]),
+    /// (o) object name
+    Object(&'a str),
+    /// (g) group names, empty for the default group
+    Groups(&'a [Cow<'a, str>]),
+    /// (s) smoothing group, `None` if turned off
+    Smoothing(Option<NonZero<u32>>),
+    /// (mtllib) material library path
+    MtlLib(&'a str),
+    /// (usemtl) material name, `None` if the material is cleared
+    UseMtl(Option<&'a str>),
+}
+
+impl Obj {
+    /// Parses OBJ file data, reporting each statement to `callback` without building an [`Obj`]
+    ///
+    /// Useful for computing derived data like bounds in a single pass over
+    /// large files. Face points are reused between faces, nothing else is
+    /// kept. Free-form and other statements are skipped.
+    pub fn parse_events<F>(bytes: &[u8], mut callback: F) -> Result<(), WobjError>
+    where
+        F: FnMut(ObjEvent<'_>),
+    {
+        (|input: &mut &BStr| parser::parse_events(input, &mut callback))
+            .parse(BStr::new(bytes))
+            .map_err(WobjError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_events() {
+        let bytes = b"mtllib a.mtl\no Cube\nv 0 0 0\nv 1 0 0\nvt 0 1\nv 0 1 0\n\
+            g a b\nusemtl Mat\ns 1\nf 1/1 2/1 -1/1\n";
+        let mut events = Vec::new();
+        Obj::parse_events(bytes, |event| events.push(format!("{event:?}"))).unwrap();
+        assert_eq!(events.len(), 10);
+        assert_eq!(events[1], r#"Object("Cube")"#);
+        assert_eq!(events[6], r#"Groups(["a", "b"])"#);
+        assert_eq!(events[7], r#"UseMtl(Some("Mat"))"#);
+        assert_eq!(events[8], "Smoothing(Some(1))");
+
+        let mut faces = Vec::new();
+        let mut bounds = [f32::MAX, f32::MIN];
+        Obj::parse_events(bytes, |event| match event {
+            ObjEvent::Vertex(v) => bounds = [bounds[0].min(v[0]), bounds[1].max(v[0])],
+            ObjEvent::Face(points) => faces.push(points.to_vec()),
+            _ => (),
+        })
+        .unwrap();
+        assert_eq!(bounds, [0.0, 1.0]);
+        let point = |v, t| FacePoint {
+            v,
+            t: Some(t),
+            n: None,
+        };
+        assert_eq!(faces, [[point(0, 0), point(1, 0), point(2, 0)]]);
+
+        assert!(Obj::parse_events(b"v 0 0 0\nf 1 1 2\n", |_| ()).is_err());
+        assert!(Obj::parse_events(b"v 0 0 0\nf 1 1/1 1\n", |_| ()).is_err());
+        assert!(Obj::parse_events(b"v 0 0 0\nf 1 1\n", |_| ()).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use super::FacePoint;
+use crate::compat::*;
+
+/// Free-form curve or surface element
+///
+/// Only stores the statements, evaluating the geometry is up to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreeForm {
+    /// (cstype) type of the curve or surface, e.g. `bspline`
+    pub cstype: String,
+    /// (cstype rat) whether the curve or surface is rational
+    pub rational: bool,
+    /// (deg) degree in the u and v directions
+    pub degree: [u32; 2],
+    /// Geometry of the element
+    pub element: FreeFormElement,
+    /// (parm u) global parameter values in the u direction
+    pub parm_u: Vec<f32>,
+    /// (parm v) global parameter values in the v direction
+    pub parm_v: Vec<f32>,
+    /// (trim/hole/scrv) trimming, hole and special curves
+    pub loops: Vec<CurveLoop>,
+    /// (sp) special points as absolute 0-based parameter vertex indices
+    pub special_points: Vec<usize>,
+}
+
+/// Free-form element geometry
+///
+/// Contains absolute 0-based indices.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FreeFormElement {
+    /// (curv) space curve over a parameter range with control vertex indices
+    Curve {
+        range: [f32; 2],
+        vertices: Vec<usize>,
+    },
+    /// (curv2) curve in parameter space with parameter vertex indices
+    Curve2 { vertices: Vec<usize> },
+    /// (surf) surface over the s and t parameter ranges with control points
+    Surface {
+        s: [f32; 2],
+        t: [f32; 2],
+        points: Vec<FacePoint<usize>>,
+    },
+}
+
+/// Loop of curves on a surface
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveLoop {
+    /// What the loop is used for
+    pub kind: CurveLoopKind,
+    /// Curve segments making up the loop
+    pub segments: Vec<CurveSegment>,
+}
+
+/// Usage of a curve loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveLoopKind {
+    /// (trim) outer trimming loop
+    Trim,
+    /// (hole) inner trimming loop
+    Hole,
+    /// (scrv) special curve
+    Special,
+}
+
+/// Segment of a curve loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurveSegment {
+    /// Parameter range of the curve
+    pub range: [f32; 2],
+    /// Index of the `curv2` element as written in the file
+    pub curve: isize,
+}
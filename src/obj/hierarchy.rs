@@ -0,0 +1,115 @@
+use super::Obj;
+use crate::compat::*;
+
+/// Objects of an OBJ with the groups nested under them, see [`Obj::hierarchy`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SceneTree<'obj> {
+    /// Objects in file order
+    pub objects: Vec<SceneObject<'obj>>,
+}
+
+/// Object node of a [`SceneTree`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SceneObject<'obj> {
+    /// Name of the object, `None` for meshes before the first `o` statement
+    pub name: Option<&'obj str>,
+    /// Groups used inside the object, in order of first use
+    pub groups: Vec<SceneGroup<'obj>>,
+    /// Indices of the object's meshes which are not part of any group,
+    /// see [`Obj::mesh`]
+    pub meshes: Vec<usize>,
+}
+
+/// Group node of a [`SceneObject`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SceneGroup<'obj> {
+    /// Name of the group
+    pub name: &'obj str,
+    /// Indices of the meshes in the group, see [`Obj::mesh`]
+    pub meshes: Vec<usize>,
+}
+
+impl Obj {
+    /// Objects of the OBJ with their groups nested under them
+    ///
+    /// Consecutive meshes of the same `o` statement form an object, and the
+    /// groups set by `g` statements inside it become its children. A mesh in
+    /// several groups is listed under each of them.
+    pub fn hierarchy(&self) -> SceneTree<'_> {
+        let mut objects: Vec<SceneObject> = Vec::new();
+        let mut previous: Option<&Option<Arc<str>>> = None;
+
+        for (index, mesh) in self.meshes.iter().enumerate() {
+            // Meshes split from the same statement share the name
+            let same_object = match (previous, &mesh.name) {
+                (Some(Some(a)), Some(b)) => Arc::ptr_eq(a, b),
+                (Some(None), None) => true,
+                _ => false,
+            };
+            previous = Some(&mesh.name);
+            if !same_object {
+                objects.push(SceneObject {
+                    name: mesh.name.as_deref(),
+                    ..Default::default()
+                });
+            }
+
+            let object = objects.last_mut().expect("an object was pushed above");
+            if mesh.groups.is_empty() {
+                object.meshes.push(index);
+            }
+            for &id in &mesh.groups {
+                let name = self.group_name(id);
+                match object.groups.iter_mut().find(|group| group.name == name) {
+                    Some(group) => group.meshes.push(index),
+                    None => object.groups.push(SceneGroup {
+                        name,
+                        meshes: vec![index],
+                    }),
+                }
+            }
+        }
+
+        SceneTree { objects }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hierarchy() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nf 1 1 1\n\
+            o Car\ng Body\nf 1 1 1\ng Wheel\nf 1 1 1\ng Body\nusemtl Paint\nf 1 1 1\n\
+            o Car\ng Wheel Axle\nf 1 1 1\n",
+        )
+        .unwrap();
+        let tree = obj.hierarchy();
+        let names: Vec<_> = tree.objects.iter().map(|o| o.name).collect();
+        assert_eq!(names, [None, Some("Car"), Some("Car")]);
+        assert_eq!(tree.objects[0].meshes, [0]);
+        assert!(tree.objects[0].groups.is_empty());
+
+        let car = &tree.objects[1];
+        assert!(car.meshes.is_empty());
+        assert_eq!(
+            car.groups,
+            [
+                SceneGroup {
+                    name: "Body",
+                    meshes: vec![1, 3]
+                },
+                SceneGroup {
+                    name: "Wheel",
+                    meshes: vec![2]
+                },
+            ]
+        );
+
+        let groups: Vec<_> = tree.objects[2].groups.iter().map(|g| g.name).collect();
+        assert_eq!(groups, ["Wheel", "Axle"]);
+        assert_eq!(tree.objects[2].groups[1].meshes, [4]);
+    }
+}
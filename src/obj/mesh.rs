@@ -1,14 +1,27 @@
-use super::{Faces, MeshData, VertexData};
+use core::num::NonZero;
+use core::ops::Range;
+
+use super::{FacePoint, Faces, FreeForm, GroupNames, MeshData, VertexData};
+use crate::compat::*;
+use crate::math;
 
 /// OBJ mesh object
+///
+/// A cheap view into the [`Obj`](crate::Obj) that can be freely copied.
+#[derive(Clone, Copy)]
 pub struct ObjMesh<'obj> {
     data: &'obj VertexData,
+    groups: &'obj GroupNames,
     mesh: &'obj MeshData,
 }
 
 impl<'obj> ObjMesh<'obj> {
-    pub(super) fn new(data: &'obj VertexData, mesh: &'obj MeshData) -> Self {
-        Self { data, mesh }
+    pub(super) fn new(
+        data: &'obj VertexData,
+        groups: &'obj GroupNames,
+        mesh: &'obj MeshData,
+    ) -> Self {
+        Self { data, groups, mesh }
     }
 
     /// Name of the mesh object
@@ -16,139 +29,874 @@ impl<'obj> ObjMesh<'obj> {
         self.mesh.name.as_deref()
     }
 
+    /// Name of the mesh object as bytes
+    pub fn name_bytes(&self) -> Option<&[u8]> {
+        self.name().map(str::as_bytes)
+    }
+
+    /// Name for displaying the mesh object
+    ///
+    /// Falls back to the first group name, then to `"unnamed"`.
+    pub fn display_name(&self) -> &str {
+        self.name()
+            .or(self.group_names().next())
+            .unwrap_or("unnamed")
+    }
+
     /// Material name of the mesh object
     pub fn material(&self) -> Option<&str> {
         self.mesh.material.as_deref()
     }
 
+    /// Material name of the mesh object as bytes
+    pub fn material_bytes(&self) -> Option<&[u8]> {
+        self.material().map(str::as_bytes)
+    }
+
+    /// Materials of the mesh object with the range of faces they apply to
+    ///
+    /// Yields a single range when parsed with the default
+    /// [`Grouping::Attributes`](crate::Grouping::Attributes).
+    pub fn material_ranges(&self) -> impl Iterator<Item = (Option<&str>, Range<usize>)> {
+        let changes = self.mesh.material_changes.iter();
+        attribute_ranges(
+            self.material(),
+            changes.map(|(start, material)| (*start, material.as_deref())),
+            self.faces().len(),
+        )
+    }
+
+    /// Smoothing groups of the mesh object with the range of faces they apply to
+    ///
+    /// Yields a single range when parsed with the default
+    /// [`Grouping::Attributes`](crate::Grouping::Attributes).
+    pub fn smoothing_ranges(&self) -> impl Iterator<Item = (Option<NonZero<u32>>, Range<usize>)> {
+        attribute_ranges(
+            self.smoothing(),
+            self.mesh.smoothing_changes.iter().copied(),
+            self.faces().len(),
+        )
+    }
+
+    /// Faces of the mesh object split by material, for one draw per material
+    ///
+    /// With the default [`Grouping::Attributes`](crate::Grouping::Attributes)
+    /// the whole mesh is borrowed as a single part.
+    pub fn submeshes_by_material(&self) -> impl Iterator<Item = (Option<&str>, Cow<'_, Faces>)> {
+        let faces = self.faces();
+        let whole = self.mesh.material_changes.is_empty();
+        self.material_ranges()
+            .map(move |(material, range)| match whole {
+                true => (material, Cow::Borrowed(faces)),
+                false => (material, Cow::Owned(faces.slice(range))),
+            })
+    }
+
     /// Relative path to the material library of the mesh object
-    pub fn mtllib(&self) -> Option<&std::path::Path> {
+    ///
+    /// Not exactly as written: separators are normalized to `/` and a
+    /// `file://` URI is reduced to its path.
+    pub fn mtllib(&self) -> Option<&str> {
         self.mesh.mtllib.as_deref()
     }
 
-    /// Names of the groups associated with the mesh object
-    pub fn groups(&self) -> &[String] {
+    /// Texture map name of the mesh object
+    pub fn usemap(&self) -> Option<&str> {
+        self.mesh.usemap.as_deref()
+    }
+
+    /// Relative path to the texture map library of the mesh object
+    ///
+    /// Normalized like [`ObjMesh::mtllib`].
+    pub fn maplib(&self) -> Option<&str> {
+        self.mesh.maplib.as_deref()
+    }
+
+    /// Ids of the groups associated with the mesh object
+    ///
+    /// The names can be looked up with [`Obj::group_name`](crate::Obj::group_name).
+    pub fn groups(&self) -> &[u32] {
         &self.mesh.groups
     }
 
-    /// Smoothing group of the mesh object
-    pub fn smoothing(&self) -> u32 {
+    /// Names of the groups associated with the mesh object
+    pub fn group_names(&self) -> impl ExactSizeIterator<Item = &'obj str> + use<'obj> {
+        let groups = self.groups;
+        self.mesh
+            .groups
+            .iter()
+            .map(|&id| groups[id as usize].as_str())
+    }
+
+    /// Smoothing group of the mesh object, `None` if smoothing is off
+    pub fn smoothing(&self) -> Option<NonZero<u32>> {
         self.mesh.smoothing
     }
 
     /// Faces of the mesh object
+    ///
+    /// Empty if the mesh object has no faces.
     pub fn faces(&self) -> &Faces {
-        // 'faces' is guaranteed by the parser to be valid
-        self.mesh.faces.as_ref().unwrap()
+        static EMPTY: Faces = Faces::V(Vec::new());
+        self.mesh.faces.as_ref().unwrap_or(&EMPTY)
+    }
+
+    /// Secondary uv indices of each face point, if the faces have them
+    ///
+    /// Some exporters write a second uv set as a fourth index, as in
+    /// `f v/t/n/t2`. The indices refer to the same `vt` data as the primary uvs.
+    pub fn secondary_uvs(&self) -> Option<&[Vec<usize>]> {
+        Some(&self.mesh.uvs2)
+            .filter(|uvs2| !uvs2.is_empty())
+            .map(Vec::as_slice)
+    }
+
+    /// Free-form curves and surfaces of the mesh object
+    pub fn free_forms(&self) -> &[FreeForm] {
+        &self.mesh.free_forms
+    }
+
+    /// Faces of the mesh object with the indices as written in the file
+    ///
+    /// The indices are 1-based and negative values are relative to the end of
+    /// the data at that point. Empty unless parsed with
+    /// [`ParseOptions::raw_indices`](crate::ParseOptions::raw_indices).
+    ///
+    /// The points format as in a face statement, so a writer can emit the face
+    /// lines with the same indexing, e.g. `f -3/1 -2/2 -1/3`.
+    pub fn raw_faces(&self) -> &[Vec<FacePoint<NonZero<isize>>>] {
+        &self.mesh.raw_faces
+    }
+
+    /// Number of triangles the faces are split into by [`ObjMesh::triangulate`]
+    ///
+    /// Computed from the face sizes without building any buffers.
+    pub fn triangle_count(&self) -> usize {
+        let faces = self.faces().iter_faces();
+        faces.map(|face| face.len().saturating_sub(2)).sum()
+    }
+
+    /// Vertex position indices of each face
+    ///
+    /// Fails if an index does not fit in a `u32`.
+    pub fn face_indices_u32(&self) -> Result<Vec<Vec<u32>>, crate::WobjError> {
+        match self.faces() {
+            Faces::V(faces) => map_indices(faces, |v| *v),
+            Faces::VT(faces) => map_indices(faces, |(v, _)| *v),
+            Faces::VN(faces) => map_indices(faces, |(v, _)| *v),
+            Faces::VTN(faces) => map_indices(faces, |(v, _, _)| *v),
+        }
+    }
+
+    /// Vertex uv indices of each face, if the faces have uvs
+    ///
+    /// Fails if an index does not fit in a `u32`.
+    pub fn face_uv_indices(&self) -> Result<Option<Vec<Vec<u32>>>, crate::WobjError> {
+        match self.faces() {
+            Faces::VT(faces) => map_indices(faces, |(_, t)| *t).map(Some),
+            Faces::VTN(faces) => map_indices(faces, |(_, t, _)| *t).map(Some),
+            Faces::V(_) | Faces::VN(_) => Ok(None),
+        }
+    }
+
+    /// Vertex normal indices of each face, if the faces have normals
+    ///
+    /// Fails if an index does not fit in a `u32`.
+    pub fn face_normal_indices(&self) -> Result<Option<Vec<Vec<u32>>>, crate::WobjError> {
+        match self.faces() {
+            Faces::VN(faces) => map_indices(faces, |(_, n)| *n).map(Some),
+            Faces::VTN(faces) => map_indices(faces, |(_, _, n)| *n).map(Some),
+            Faces::V(_) | Faces::VT(_) => Ok(None),
+        }
+    }
+
+    /// Guess the winding order of front faces
+    ///
+    /// Uses the signed volume of the mesh, so only closed meshes can be
+    /// detected. Returns [`Winding::Unknown`] for open or flat meshes.
+    pub fn detect_front_face(&self) -> Winding {
+        let faces = self.position_faces();
+
+        // Every edge of a closed mesh is shared by two faces in opposite directions
+        let mut edges = Map::default();
+        for face in &faces {
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                if a != b {
+                    *edges.entry((a.min(b), a.max(b))).or_insert(0) += if a < b { 1 } else { -1 };
+                }
+            }
+        }
+        if edges.values().any(|count: &i32| *count != 0) {
+            return Winding::Unknown;
+        }
+
+        let mut volume = 0.0;
+        for face in &faces {
+            let points = face
+                .iter()
+                .filter_map(|v| self.data.vertex.get(*v))
+                .collect::<Vec<_>>();
+
+            for i in 2..points.len() {
+                let (a, b, c) = (*points[0], *points[i - 1], *points[i]);
+                volume += math::dot(a, math::cross(b, c)) as f64;
+            }
+        }
+
+        if volume > f64::EPSILON {
+            Winding::CounterClockwise
+        } else if volume < -f64::EPSILON {
+            Winding::Clockwise
+        } else {
+            Winding::Unknown
+        }
+    }
+
+    /// Unit normal of each face computed with Newell's method
+    pub fn face_normals(&self) -> Vec<[f32; 3]> {
+        self.position_faces()
+            .iter()
+            .map(|face| math::normalize(self.newell(face)))
+            .collect()
+    }
+
+    /// Estimate smooth vertex normals without triangulating
+    ///
+    /// Returns one normal for each vertex position of the [`Obj`](crate::Obj),
+    /// averaged from the area weighted normals of the faces using it.
+    /// Positions not used by the mesh get a zero normal.
+    pub fn approximate_normals(&self) -> Vec<[f32; 3]> {
+        let mut normals = vec![[0.0; 3]; self.data.vertex.len()];
+        accumulate_normals(
+            &self.data.vertex,
+            self.faces(),
+            NormalWeighting::Area,
+            &mut normals,
+        );
+        normals.into_iter().map(math::normalize).collect()
+    }
+
+    /// Vertex position indices of each face
+    fn position_faces(&self) -> Vec<Vec<usize>> {
+        self.faces()
+            .iter_faces()
+            .map(|face| face.map(|p| p.v).collect())
+            .collect()
+    }
+
+    /// Newell normal of a face from position indices
+    fn newell(&self, face: &[usize]) -> [f32; 3] {
+        math::newell(
+            face.iter()
+                .filter_map(|v| self.data.vertex.get(*v).copied()),
+        )
     }
 
     #[cfg(feature = "trimesh")]
     /// Create a triangulated mesh from faces
     pub fn triangulate(&self) -> Result<(Indicies, Vertices), crate::WobjError> {
-        use std::hash::Hash;
+        let (indices, vertices, _) = self.triangulate_with(TriangulateOptions::default())?;
+        Ok((indices, vertices))
+    }
 
-        use ahash::RandomState;
-        use indexmap::IndexSet;
+    #[cfg(feature = "trimesh")]
+    /// Create a triangulated mesh from faces with the specified options
+    ///
+    /// Also returns the number of degenerate triangles found, whether they
+    /// were skipped or not.
+    pub fn triangulate_with(
+        &self,
+        options: TriangulateOptions,
+    ) -> Result<(Indicies, Vertices, usize), crate::WobjError> {
+        self.triangulate_faces(options, None)
+    }
 
-        let faces = self.faces();
-        let mut indices = Vec::with_capacity(faces.len() * 3);
+    #[cfg(feature = "trimesh")]
+    /// Create a triangulated mesh from faces, with the source face of each triangle
+    ///
+    /// The returned face indices are parallel to the triangles, so a hit on
+    /// triangle `i` can be mapped back to the face `sources[i]` in [`ObjMesh::faces`].
+    pub fn triangulate_with_source(
+        &self,
+        options: TriangulateOptions,
+    ) -> Result<(Indicies, Vertices, Vec<usize>), crate::WobjError> {
+        let mut sources = Vec::with_capacity(self.triangle_count());
+        let (indices, vertices, _) = self.triangulate_faces(options, Some(&mut sources))?;
+        Ok((indices, vertices, sources))
+    }
 
-        fn collect<T>(indices: &mut Vec<usize>, faces: &Vec<Vec<T>>) -> IndexSet<T, RandomState>
-        where
-            T: Clone + Hash + Eq,
-        {
-            let mut points = IndexSet::with_capacity_and_hasher(indices.len(), RandomState::new());
-
-            // Triangulate faces
-            for face in faces {
-                // the parser guarantees that there are at least 3 points
-                for i in 2..face.len() {
-                    let (a, b, c) = (0, i - 1, i);
-                    indices.push(points.insert_full(face[a].clone()).0);
-                    indices.push(points.insert_full(face[b].clone()).0);
-                    indices.push(points.insert_full(face[c].clone()).0);
-                }
-            }
+    #[cfg(feature = "trimesh")]
+    /// Create a triangulated mesh with adjacency, as used by `GL_TRIANGLES_ADJACENCY`
+    ///
+    /// Each triangle takes 6 indices: its corners, each followed by the vertex
+    /// opposite the next edge in the neighbouring triangle. Neighbours are found
+    /// by position, so uv and normal seams do not separate them. An edge without
+    /// a neighbour refers to the opposite corner of its own triangle.
+    pub fn triangulate_adjacency(&self) -> Result<(Indicies, Vertices), crate::WobjError> {
+        let (indices, vertices) = self.triangulate()?;
+        let position = |i: usize| vertices.positions[i].map(f32::to_bits);
 
-            points
+        // Directed edge to the corner opposite it
+        let mut opposite: Map<([u32; 3], [u32; 3]), usize> = Map::default();
+        for t in indices.0.chunks_exact(3) {
+            for k in 0..3 {
+                let edge = (position(t[k]), position(t[(k + 1) % 3]));
+                opposite.entry(edge).or_insert(t[(k + 2) % 3]);
+            }
         }
 
-        const ERROR_OOB_VERTEX: &str = "vertex index is out of range";
-        const ERROR_OOB_NORMAL: &str = "normal index is out of range";
-        const ERROR_OOB_UV: &str = "uv index is out of range";
+        let mut adjacency = Vec::with_capacity(indices.0.len() * 2);
+        for t in indices.0.chunks_exact(3) {
+            for k in 0..3 {
+                let (a, b, c) = (t[k], t[(k + 1) % 3], t[(k + 2) % 3]);
+                let neighbour = opposite.get(&(position(b), position(a)));
+                adjacency.extend([a, neighbour.copied().unwrap_or(c)]);
+            }
+        }
+        Ok((Indicies(adjacency), vertices))
+    }
 
-        // Turn point indexes into vertices
-        let vertices = match faces {
-            Faces::V(faces) => {
-                let points = collect(&mut indices, faces);
+    #[cfg(feature = "trimesh")]
+    /// Create a triangulated mesh from faces, borrowing the vertex data where possible
+    ///
+    /// Returns the same result as [`ObjMesh::triangulate`]. When the face points
+    /// use consecutive elements of the vertex data in the order they first
+    /// appear, as most exporters write them, the vertices borrow those elements
+    /// from the [`Obj`](crate::Obj) instead of copying them.
+    pub fn triangulate_borrowed(
+        &self,
+    ) -> Result<(Indicies, BorrowedVertices<'obj>), crate::WobjError> {
+        fn get<T>(data: &[T], start: usize, count: usize) -> Option<&[T]> {
+            data.get(start..start.checked_add(count)?)
+        }
 
-                let mut positions = Vec::with_capacity(points.len());
-                for v in points {
-                    positions.push(*self.data.vertex.get(v).ok_or(ERROR_OOB_VERTEX)?);
-                }
+        let borrowed = self.sequential_points().and_then(|(start, count)| {
+            Some(BorrowedVertices {
+                positions: Cow::Borrowed(get(&self.data.vertex, start.v, count)?),
+                normals: match start.n {
+                    Some(n) => Some(Cow::Borrowed(get(&self.data.normal, n, count)?)),
+                    None => None,
+                },
+                uvs: match start.t {
+                    Some(t) => Some(Cow::Borrowed(get(&self.data.texture, t, count)?)),
+                    None => None,
+                },
+                uvs2: None,
+            })
+            .map(|vertices| (start.v, vertices))
+        });
+        let Some((start, vertices)) = borrowed else {
+            let (indices, vertices) = self.triangulate()?;
+            return Ok((indices, vertices.into()));
+        };
 
-                Vertices {
-                    positions,
-                    normals: None,
-                    uvs: None,
-                }
+        let mut indices = Vec::with_capacity(self.triangle_count() * 3);
+        for face in self.faces().iter_faces() {
+            let face: Vec<_> = face.map(|point| point.v - start).collect();
+            for i in 2..face.len() {
+                indices.extend([face[0], face[i - 1], face[i]]);
             }
-            Faces::VT(faces) => {
-                let points = collect(&mut indices, faces);
-
-                let mut positions = Vec::with_capacity(points.len());
-                let mut uvs = Vec::with_capacity(points.len());
-                for (v, t) in points {
-                    positions.push(*self.data.vertex.get(v).ok_or(ERROR_OOB_VERTEX)?);
-                    uvs.push(*self.data.texture.get(t).ok_or(ERROR_OOB_UV)?);
-                }
+        }
+        Ok((Indicies(indices), vertices))
+    }
 
-                Vertices {
-                    positions,
-                    normals: None,
-                    uvs: Some(uvs),
-                }
+    #[cfg(feature = "trimesh")]
+    /// First face point and the number of points, if the points refer to
+    /// consecutive data elements in the order they first appear
+    fn sequential_points(&self) -> Option<(FacePoint<usize>, usize)> {
+        if !self.mesh.uvs2.is_empty() {
+            return None;
+        }
+        let faces = self.faces();
+        let start = faces.iter_faces().next()?.next()?;
+        let mut count = 0;
+        for point in faces.iter_faces().flatten() {
+            // Offset from the start, shared by all elements of the point
+            let offset = point.v.checked_sub(start.v)?;
+            let follows = |i: Option<usize>, start: Option<usize>| i == start.map(|s| s + offset);
+            if offset > count || !follows(point.t, start.t) || !follows(point.n, start.n) {
+                return None;
             }
-            Faces::VN(faces) => {
-                let points = collect(&mut indices, faces);
-
-                let mut positions = Vec::with_capacity(points.len());
-                let mut normals = Vec::with_capacity(points.len());
-                for (v, n) in points {
-                    positions.push(*self.data.vertex.get(v).ok_or(ERROR_OOB_VERTEX)?);
-                    normals.push(*self.data.normal.get(n).ok_or(ERROR_OOB_NORMAL)?);
+            count = count.max(offset + 1);
+        }
+        Some((start, count))
+    }
+
+    #[cfg(feature = "trimesh")]
+    fn triangulate_faces(
+        &self,
+        options: TriangulateOptions,
+        sources: Option<&mut Vec<usize>>,
+    ) -> Result<(Indicies, Vertices, usize), crate::WobjError> {
+        let mut points =
+            Set::with_capacity_and_hasher(self.faces().len() * 3, RandomState::default());
+        let (indices, degenerate) = self.triangulate_points(options, sources, &mut points);
+        let vertices = point_vertices(self.data, points)?;
+        Ok((Indicies(indices), vertices, degenerate))
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Triangulates the faces into indices of `points`, adding the new points
+    ///
+    /// Also returns the number of degenerate triangles.
+    pub(super) fn triangulate_points(
+        &self,
+        options: TriangulateOptions,
+        mut sources: Option<&mut Vec<usize>>,
+        points: &mut Points,
+    ) -> (Vec<usize>, usize) {
+        let faces = self.faces();
+        let mut indices = Vec::with_capacity(faces.len() * 3);
+        let mut degenerate = 0;
+
+        // Triangulate faces
+        for (source, face) in faces.iter_faces().enumerate() {
+            // Points are unique by their secondary uv too
+            let uvs2 = self.mesh.uvs2.get(source);
+            let mut face: Vec<_> = face
+                .enumerate()
+                .map(|(i, point)| (point, uvs2.map(|uvs2| uvs2[i])))
+                .collect();
+            let pivot = match options.fan {
+                FanPivot::First => 0,
+                FanPivot::BestAspect => self.best_pivot(face.iter().map(|p| p.0.v)),
+            };
+            face.rotate_left(pivot);
+
+            match options.topology {
+                Topology::List => {
+                    // the parser guarantees that there are at least 3 points
+                    for i in 2..face.len() {
+                        let triangle = [face[0], face[i - 1], face[i]];
+                        if self.is_degenerate(triangle.map(|p| p.0.v), options.epsilon) {
+                            degenerate += 1;
+                            if options.skip_degenerate {
+                                continue;
+                            }
+                        }
+                        indices.extend(triangle.map(|point| points.insert_full(point).0));
+                        if let Some(sources) = &mut sources {
+                            sources.push(source);
+                        }
+                    }
                 }
+                Topology::Strip => {
+                    // Zigzag between both ends of the face: 0, 1, n-1, 2, n-2, ...
+                    let (mut front, mut back) = (1, face.len() - 1);
+                    let mut strip = vec![points.insert_full(face[0]).0];
+                    while front <= back {
+                        strip.push(points.insert_full(face[front]).0);
+                        front += 1;
+                        if front <= back {
+                            strip.push(points.insert_full(face[back]).0);
+                            back -= 1;
+                        }
+                    }
+                    for i in 2..face.len() {
+                        let triangle = [strip[i - 2], strip[i - 1], strip[i]];
+                        let positions = triangle.map(|i| points[i].0.v);
+                        degenerate += self.is_degenerate(positions, options.epsilon) as usize;
+                    }
 
-                Vertices {
-                    positions,
-                    normals: Some(normals),
-                    uvs: None,
+                    // Join with the previous face through degenerate triangles,
+                    // keeping the winding parity of the new face
+                    let start = indices.len();
+                    if let Some(&last) = indices.last() {
+                        if indices.len() % 2 == 1 {
+                            indices.push(last);
+                        }
+                        indices.extend([last, strip[0]]);
+                    }
+                    indices.extend(strip);
+                    if let Some(sources) = &mut sources {
+                        let triangles = indices.len() - start.max(2);
+                        sources.extend(core::iter::repeat_n(source, triangles));
+                    }
                 }
             }
-            Faces::VTN(faces) => {
-                let points = collect(&mut indices, faces);
-
-                let mut positions = Vec::with_capacity(points.len());
-                let mut normals = Vec::with_capacity(points.len());
-                let mut uvs = Vec::with_capacity(points.len());
-                for (v, t, n) in points {
-                    positions.push(*self.data.vertex.get(v).ok_or(ERROR_OOB_VERTEX)?);
-                    normals.push(*self.data.normal.get(n).ok_or(ERROR_OOB_NORMAL)?);
-                    uvs.push(*self.data.texture.get(t).ok_or(ERROR_OOB_UV)?);
-                }
+        }
 
-                Vertices {
-                    positions,
-                    normals: Some(normals),
-                    uvs: Some(uvs),
-                }
+        (indices, degenerate)
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Create a polygon mesh from faces, keeping quads and n-gons as they are
+    ///
+    /// The points of all faces are written to the indices in order, and the
+    /// returned counts give the number of points of each face. Vertices are
+    /// combined the same way as by [`ObjMesh::triangulate`].
+    pub fn polygons(&self) -> Result<(Indicies, Vertices, Vec<u32>), crate::WobjError> {
+        let faces = self.faces();
+        let mut indices = Vec::with_capacity(faces.len() * 4);
+        let mut points = Set::with_capacity_and_hasher(indices.capacity(), RandomState::default());
+        let mut counts = Vec::with_capacity(faces.len());
+
+        for (source, face) in faces.iter_faces().enumerate() {
+            let uvs2 = self.mesh.uvs2.get(source);
+            counts.push(face.len() as u32);
+            indices.extend(face.enumerate().map(|(i, point)| {
+                let uv2 = uvs2.map(|uvs2| uvs2[i]);
+                points.insert_full((point, uv2)).0
+            }));
+        }
+
+        let vertices = point_vertices(self.data, points)?;
+        Ok((Indicies(indices), vertices, counts))
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Create a triangulated mesh keeping separate position, uv and normal indices
+    ///
+    /// Unlike [`ObjMesh::triangulate`] the face points are not combined into
+    /// vertices, so positions shared by points with different uvs or normals
+    /// are not duplicated. Only the elements used by the mesh are kept.
+    pub fn indexed(&self) -> Result<IndexedMesh, crate::WobjError> {
+        let faces = self.faces();
+        let mut triangles = Vec::with_capacity(faces.len() * 3);
+        for face in faces.iter_faces() {
+            let face: Vec<_> = face.collect();
+            for i in 2..face.len() {
+                triangles.extend([face[0], face[i - 1], face[i]]);
             }
+        }
+
+        let (positions, pos_idx) = compact(
+            &self.data.vertex,
+            triangles.iter().map(|p| p.v),
+            "vertex index is out of range",
+        )?;
+        let (uvs, uv_idx) = compact(
+            &self.data.texture,
+            triangles.iter().filter_map(|p| p.t),
+            "uv index is out of range",
+        )?;
+        let (normals, normal_idx) = compact(
+            &self.data.normal,
+            triangles.iter().filter_map(|p| p.n),
+            "normal index is out of range",
+        )?;
+
+        Ok(IndexedMesh {
+            positions,
+            uvs,
+            normals,
+            pos_idx,
+            uv_idx,
+            normal_idx,
+        })
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Fan pivot giving the best worst-case triangle quality
+    fn best_pivot(&self, face: impl Iterator<Item = usize>) -> usize {
+        let positions: Option<Vec<_>> = face.map(|v| self.data.vertex.get(v).copied()).collect();
+        let Some(positions) = positions else {
+            // Out of range indices are reported as errors instead
+            return 0;
         };
 
-        Ok((Indicies(indices), vertices))
+        let n = positions.len();
+        let quality = |pivot: usize| {
+            let point = |i: usize| positions[(pivot + i) % n];
+            (2..n)
+                .map(|i| triangle_quality(point(0), point(i - 1), point(i)))
+                .fold(f32::INFINITY, f32::min)
+        };
+        (0..n)
+            .map(|pivot| (pivot, quality(pivot)))
+            .fold(
+                (0, f32::NEG_INFINITY),
+                |best, c| if c.1 > best.1 { c } else { best },
+            )
+            .0
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Whether a triangle has repeated positions or an area of at most `epsilon`
+    fn is_degenerate(&self, [a, b, c]: [usize; 3], epsilon: f32) -> bool {
+        if a == b || b == c || a == c {
+            return true;
+        }
+
+        // Out of range indices are reported as errors instead
+        let vertex = &self.data.vertex;
+        let (Some(&a), Some(&b), Some(&c)) = (vertex.get(a), vertex.get(b), vertex.get(c)) else {
+            return false;
+        };
+        let area = math::length(math::cross(math::sub(b, a), math::sub(c, a))) / 2.0;
+        area <= epsilon
     }
 }
 
+/// OBJ mesh object owning its faces, see [`Obj::into_meshes`](crate::Obj::into_meshes)
+///
+/// The vertex data and group names are shared between the meshes of an OBJ.
+#[derive(Debug, Clone)]
+pub struct OwnedMesh {
+    data: Arc<VertexData>,
+    groups: Arc<GroupNames>,
+    mesh: MeshData,
+}
+
+impl OwnedMesh {
+    pub(super) fn new(data: Arc<VertexData>, groups: Arc<GroupNames>, mesh: MeshData) -> Self {
+        Self { data, groups, mesh }
+    }
+
+    /// Borrows the mesh for the accessors of [`ObjMesh`]
+    pub fn as_mesh(&self) -> ObjMesh<'_> {
+        ObjMesh::new(&self.data, &self.groups, &self.mesh)
+    }
+
+    /// All vertex positions of the OBJ
+    pub fn vertices(&self) -> &[[f32; 3]] {
+        &self.data.vertex
+    }
+
+    /// All vertex normals of the OBJ
+    pub fn normals(&self) -> &[[f32; 3]] {
+        &self.data.normal
+    }
+
+    /// All vertex uvs of the OBJ
+    pub fn uvs(&self) -> &[[f32; 2]] {
+        &self.data.texture
+    }
+
+    /// Takes the faces of the mesh
+    pub fn into_faces(self) -> Faces {
+        self.mesh.faces.unwrap_or(Faces::V(Vec::new()))
+    }
+
+    /// Takes the free-form curves and surfaces of the mesh
+    pub fn into_free_forms(self) -> Vec<FreeForm> {
+        self.mesh.free_forms
+    }
+}
+
+#[cfg(feature = "trimesh")]
+/// Options for [`ObjMesh::triangulate_with`]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TriangulateOptions {
+    /// Leave out degenerate triangles instead of keeping them
+    ///
+    /// Only applies to [`Topology::List`], strips keep all triangles.
+    pub skip_degenerate: bool,
+    /// Area at or below which a triangle is degenerate
+    ///
+    /// Triangles with repeated vertex positions are always degenerate.
+    pub epsilon: f32,
+    /// Primitive topology of the output indices
+    pub topology: Topology,
+    /// Point of each face the triangles start from
+    pub fan: FanPivot,
+}
+
+#[cfg(feature = "trimesh")]
+/// Primitive topology of triangulated indices
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    /// Every three indices form a triangle
+    #[default]
+    List,
+    /// Every index forms a triangle with the two before it
+    ///
+    /// Faces are joined into a single strip through degenerate triangles,
+    /// and the winding alternates as usual for strips.
+    Strip,
+}
+
+#[cfg(feature = "trimesh")]
+/// Starting point for triangulating faces
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FanPivot {
+    /// Start from the first point of the face
+    #[default]
+    First,
+    /// Start from the point which avoids thin sliver triangles the most
+    ///
+    /// Compares every starting point, so it is slower for large faces.
+    BestAspect,
+}
+
+#[cfg(feature = "trimesh")]
+/// Shape quality of a triangle, 1 for equilateral and 0 for degenerate
+fn triangle_quality(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> f32 {
+    let area = math::length(math::cross(math::sub(b, a), math::sub(c, a))) / 2.0;
+    let edges = [math::sub(b, a), math::sub(c, b), math::sub(a, c)];
+    let squared: f32 = edges.iter().map(|e| math::dot(*e, *e)).sum();
+    match squared > 0.0 {
+        true => 4.0 * math::sqrt(3.0) * area / squared,
+        false => 0.0,
+    }
+}
+
+/// Winding order of front faces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    /// Front faces have clockwise vertices
+    Clockwise,
+    /// Front faces have counter-clockwise vertices
+    CounterClockwise,
+    /// Winding could not be determined
+    Unknown,
+}
+
+/// Weighting of face normals when averaging them into vertex normals
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// Larger faces contribute more
+    #[default]
+    Area,
+    /// Faces contribute by the angle of their corner at the vertex
+    Angle,
+    /// Every face contributes equally
+    Uniform,
+}
+
+/// Adds the weighted normals of the faces to the normals of their vertex positions
+pub(super) fn accumulate_normals(
+    vertex: &[[f32; 3]],
+    faces: &Faces,
+    weighting: NormalWeighting,
+    normals: &mut [[f32; 3]],
+) {
+    for face in faces.iter_faces() {
+        let face: Vec<usize> = face.map(|p| p.v).collect();
+        let positions: Vec<[f32; 3]> = face
+            .iter()
+            .filter_map(|v| vertex.get(*v).copied())
+            .collect();
+        if positions.len() != face.len() {
+            continue;
+        }
+
+        let (_, corners) = corner_normals(&positions, weighting);
+        for (v, weighted) in face.iter().zip(corners) {
+            normals[*v] = math::add(normals[*v], weighted);
+        }
+    }
+}
+
+/// Unit normal of a face and its weighted normal at each corner
+pub(super) fn corner_normals(
+    positions: &[[f32; 3]],
+    weighting: NormalWeighting,
+) -> ([f32; 3], Vec<[f32; 3]>) {
+    let area = math::newell(positions.iter().copied());
+    let unit = math::normalize(area);
+    let corners = (0..positions.len()).map(|i| match weighting {
+        NormalWeighting::Area => area,
+        NormalWeighting::Uniform => unit,
+        NormalWeighting::Angle => {
+            let prev = positions[(i + positions.len() - 1) % positions.len()];
+            let next = positions[(i + 1) % positions.len()];
+            let corner = math::angle(math::sub(prev, positions[i]), math::sub(next, positions[i]));
+            math::scale(unit, corner)
+        }
+    });
+    (unit, corners.collect())
+}
+
+#[cfg(feature = "trimesh")]
+/// Copies the used elements, returning them with the remapped indices
+fn compact<T: Copy>(
+    elements: &[T],
+    indices: impl Iterator<Item = usize>,
+    error: &'static str,
+) -> Result<(Vec<T>, Vec<usize>), crate::WobjError> {
+    let mut used = Set::with_hasher(RandomState::default());
+    let indices = indices.map(|i| used.insert_full(i).0).collect();
+    let elements = used
+        .into_iter()
+        .map(|i| elements.get(i).copied().ok_or(error))
+        .collect::<Result<_, _>>()?;
+    Ok((elements, indices))
+}
+
+/// Splits `len` faces into ranges at the attribute changes
+fn attribute_ranges<T: Copy>(
+    initial: T,
+    changes: impl Iterator<Item = (usize, T)> + Clone,
+    len: usize,
+) -> impl Iterator<Item = (T, Range<usize>)> {
+    let starts = core::iter::once((0, initial)).chain(changes.clone());
+    let ends = changes.map(|(start, _)| start).chain(core::iter::once(len));
+
+    starts
+        .zip(ends)
+        .map(|((start, value), end)| (value, start..end))
+        .filter(|(_, range)| !range.is_empty())
+}
+
+/// Collects one index of each face point
+fn map_indices<T>(
+    faces: &[Vec<T>],
+    index: impl Fn(&T) -> usize,
+) -> Result<Vec<Vec<u32>>, crate::WobjError> {
+    faces
+        .iter()
+        .map(|face| face.iter().map(|p| u32::try_from(index(p))).collect())
+        .collect::<Result<_, _>>()
+        .map_err(|_| "too many vertices for u32 indices".into())
+}
+
+#[cfg(feature = "trimesh")]
+/// Unique face points with their secondary uv
+pub(super) type Points = Set<(FacePoint<usize>, Option<usize>)>;
+
+#[cfg(feature = "trimesh")]
+/// Turns unique points into vertices
+///
+/// An attribute is included if any point has it, the points without it get
+/// zeros.
+pub(super) fn point_vertices(
+    data: &VertexData,
+    points: Points,
+) -> Result<Vertices, crate::WobjError> {
+    const ERROR_OOB_VERTEX: &str = "vertex index is out of range";
+    const ERROR_OOB_NORMAL: &str = "normal index is out of range";
+    const ERROR_OOB_UV: &str = "uv index is out of range";
+
+    fn with<T>(has: bool, len: usize) -> Option<Vec<T>> {
+        has.then(|| Vec::with_capacity(len))
+    }
+    let len = points.len();
+    let mut positions = Vec::with_capacity(len);
+    let mut normals = with(points.iter().any(|(point, _)| point.n.is_some()), len);
+    let mut uvs = with(points.iter().any(|(point, _)| point.t.is_some()), len);
+    let mut uvs2 = with(points.iter().any(|(_, uv2)| uv2.is_some()), len);
+    for (point, uv2) in points {
+        positions.push(*data.vertex.get(point.v).ok_or(ERROR_OOB_VERTEX)?);
+        if let Some(normals) = &mut normals {
+            normals.push(match point.n {
+                Some(n) => *data.normal.get(n).ok_or(ERROR_OOB_NORMAL)?,
+                None => [0.0; 3],
+            });
+        }
+        if let Some(uvs) = &mut uvs {
+            uvs.push(match point.t {
+                Some(t) => *data.texture.get(t).ok_or(ERROR_OOB_UV)?,
+                None => [0.0; 2],
+            });
+        }
+        if let Some(uvs2) = &mut uvs2 {
+            uvs2.push(match uv2 {
+                Some(t) => *data.texture.get(t).ok_or(ERROR_OOB_UV)?,
+                None => [0.0; 2],
+            });
+        }
+    }
+
+    Ok(Vertices {
+        positions,
+        normals,
+        uvs,
+        uvs2,
+    })
+}
+
 #[cfg(feature = "trimesh")]
 /// Triangulated mesh indicies
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -164,4 +912,691 @@ pub struct Vertices {
     pub normals: Option<Vec<[f32; 3]>>,
     /// Vertex UVs
     pub uvs: Option<Vec<[f32; 2]>>,
+    /// Secondary vertex UVs, see [`ObjMesh::secondary_uvs`]
+    pub uvs2: Option<Vec<[f32; 2]>>,
+}
+
+#[cfg(feature = "trimesh")]
+/// Triangulated mesh verticies borrowed from the [`Obj`](crate::Obj) where possible,
+/// see [`ObjMesh::triangulate_borrowed`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BorrowedVertices<'a> {
+    /// Vertex positions
+    pub positions: Cow<'a, [[f32; 3]]>,
+    /// Vertex normals
+    pub normals: Option<Cow<'a, [[f32; 3]]>>,
+    /// Vertex UVs
+    pub uvs: Option<Cow<'a, [[f32; 2]]>>,
+    /// Secondary vertex UVs, see [`ObjMesh::secondary_uvs`]
+    pub uvs2: Option<Cow<'a, [[f32; 2]]>>,
+}
+
+#[cfg(feature = "trimesh")]
+impl BorrowedVertices<'_> {
+    /// Whether the positions are borrowed rather than copied
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.positions, Cow::Borrowed(_))
+    }
+
+    /// Copies any borrowed data into owned verticies
+    pub fn into_owned(self) -> Vertices {
+        Vertices {
+            positions: self.positions.into_owned(),
+            normals: self.normals.map(Cow::into_owned),
+            uvs: self.uvs.map(Cow::into_owned),
+            uvs2: self.uvs2.map(Cow::into_owned),
+        }
+    }
+}
+
+#[cfg(feature = "trimesh")]
+impl From<Vertices> for BorrowedVertices<'_> {
+    fn from(vertices: Vertices) -> Self {
+        Self {
+            positions: Cow::Owned(vertices.positions),
+            normals: vertices.normals.map(Cow::Owned),
+            uvs: vertices.uvs.map(Cow::Owned),
+            uvs2: vertices.uvs2.map(Cow::Owned),
+        }
+    }
+}
+
+#[cfg(feature = "trimesh")]
+/// Triangulated mesh with separate index streams, see [`ObjMesh::indexed`]
+///
+/// Every three indices of a stream form a triangle. The uv and normal index
+/// streams are either empty or parallel to the position indices.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IndexedMesh {
+    /// Vertex positions
+    pub positions: Vec<[f32; 3]>,
+    /// Vertex uvs
+    pub uvs: Vec<[f32; 2]>,
+    /// Vertex normals
+    pub normals: Vec<[f32; 3]>,
+    /// Position index of each triangle corner
+    pub pos_idx: Vec<usize>,
+    /// Uv index of each triangle corner
+    pub uv_idx: Vec<usize>,
+    /// Normal index of each triangle corner
+    pub normal_idx: Vec<usize>,
+}
+
+#[cfg(feature = "trimesh")]
+/// Interleaved triangulated mesh vertex
+///
+/// The layout is fixed for uploading to the GPU: 32 bytes with the position
+/// at offset 0, the normal at offset 12 and the uv at offset 24. With the
+/// `bytemuck` feature it implements [`bytemuck::Pod`].
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+pub struct Vertex {
+    /// Vertex position
+    pub position: [f32; 3],
+    /// Vertex normal, [`Vertex::DEFAULT_NORMAL`] if the mesh has no normals
+    pub normal: [f32; 3],
+    /// Vertex uv, zero if the mesh has no uvs
+    pub uv: [f32; 2],
+}
+
+#[cfg(feature = "trimesh")]
+impl Vertex {
+    /// Normal of interleaved vertices whose mesh has no normals, facing `+Z`
+    pub const DEFAULT_NORMAL: [f32; 3] = [0.0, 0.0, 1.0];
+}
+
+#[cfg(feature = "trimesh")]
+impl Vertices {
+    /// Interleaves the vertex attributes
+    pub fn interleaved(&self) -> Vec<Vertex> {
+        (0..self.positions.len())
+            .map(|i| Vertex {
+                position: self.positions[i],
+                normal: self
+                    .normals
+                    .as_ref()
+                    .map_or(Vertex::DEFAULT_NORMAL, |n| n[i]),
+                uv: self.uvs.as_ref().map_or([0.0; 2], |t| t[i]),
+            })
+            .collect()
+    }
+
+    /// Merges vertices with positions within `epsilon` of each other
+    ///
+    /// The first vertex of each merged set is kept with its normal and uv.
+    /// The indices are rewritten to the remaining vertices.
+    ///
+    /// Fails without changes if an index is out of range or the attributes
+    /// differ in length from the positions.
+    pub fn weld(&mut self, indices: &mut Indicies, epsilon: f32) -> Result<(), crate::WobjError> {
+        self.check(indices)?;
+
+        // Cells are at least epsilon wide, so close points are in neighbouring cells
+        let cell_size = epsilon.max(f32::MIN_POSITIVE);
+        let cell = |p: [f32; 3]| p.map(|c| math::floor(c / cell_size) as i64);
+
+        let mut grid: Map<[i64; 3], Vec<usize>> = Default::default();
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut kept = Vec::new();
+
+        for (i, &p) in self.positions.iter().enumerate() {
+            let [x, y, z] = cell(p);
+            let neighbours = (-1..=1)
+                .flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [dx, dy, dz])));
+            let existing = neighbours
+                .filter_map(|[dx, dy, dz]| grid.get(&[x + dx, y + dy, z + dz]))
+                .flatten()
+                .find(|&&k| math::length(math::sub(self.positions[kept[k]], p)) <= epsilon);
+
+            match existing {
+                Some(&k) => remap.push(k),
+                None => {
+                    grid.entry([x, y, z]).or_default().push(kept.len());
+                    remap.push(kept.len());
+                    kept.push(i);
+                }
+            }
+        }
+
+        self.positions = kept.iter().map(|&i| self.positions[i]).collect();
+        if let Some(normals) = &mut self.normals {
+            *normals = kept.iter().map(|&i| normals[i]).collect();
+        }
+        if let Some(uvs) = &mut self.uvs {
+            *uvs = kept.iter().map(|&i| uvs[i]).collect();
+        }
+        if let Some(uvs2) = &mut self.uvs2 {
+            *uvs2 = kept.iter().map(|&i| uvs2[i]).collect();
+        }
+        indices.0.iter_mut().for_each(|i| *i = remap[*i]);
+        Ok(())
+    }
+
+    /// Checks that the indices and attributes match the positions
+    pub(super) fn check(&self, indices: &Indicies) -> Result<(), crate::WobjError> {
+        let len = self.positions.len();
+        let attributes = [
+            self.normals.as_ref().map(Vec::len),
+            self.uvs.as_ref().map(Vec::len),
+            self.uvs2.as_ref().map(Vec::len),
+        ];
+        if attributes.into_iter().flatten().any(|n| n != len) {
+            return Err("vertex attributes differ in length from the positions".into());
+        }
+        if indices.0.iter().any(|&i| i >= len) {
+            return Err("vertex index is out of range".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::ObjMesh;
+    use crate::obj::{Faces, GroupNames, MeshData, VertexData};
+    use crate::{Grouping, Obj, ParseOptions, Winding};
+
+    const CUBE_VERTICES: &str = "v 1 1 -1\nv 1 -1 -1\nv 1 1 1\nv 1 -1 1\n\
+        v -1 1 -1\nv -1 -1 -1\nv -1 1 1\nv -1 -1 1\n";
+    const CUBE_FACES: [&str; 6] = [
+        "f 1 5 7 3\n",
+        "f 4 3 7 8\n",
+        "f 8 7 5 6\n",
+        "f 6 2 4 8\n",
+        "f 2 1 3 4\n",
+        "f 6 5 1 2\n",
+    ];
+
+    #[test]
+    fn mesh_without_faces() {
+        let (data, mesh) = (VertexData::default(), MeshData::default());
+        let groups = GroupNames::default();
+        let mesh = ObjMesh::new(&data, &groups, &mesh);
+
+        assert!(mesh.faces().is_empty());
+        assert!(mesh.face_indices_u32().unwrap().is_empty());
+        assert_eq!(mesh.material_ranges().count(), 0);
+        assert_eq!(mesh.detect_front_face(), Winding::Unknown);
+        #[cfg(feature = "trimesh")]
+        assert!(mesh.triangulate().unwrap().0.0.is_empty());
+    }
+
+    #[test]
+    fn submeshes_by_material() {
+        let bytes = b"v 0 0 0\no Car\nusemtl Paint\nf 1 1 1\nf 1 1 1\nusemtl Glass\nf 1 1 1\n";
+        let options = ParseOptions {
+            grouping: Grouping::Object,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let mesh = obj.meshes()[0];
+
+        let parts: Vec<_> = mesh.submeshes_by_material().collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0, Some("Paint"));
+        assert_eq!(parts[0].1.len(), 2);
+        assert_eq!(parts[1].0, Some("Glass"));
+        assert_eq!(parts[1].1.as_ref(), &Faces::V(vec![vec![0, 0, 0]]));
+        assert_eq!(mesh.name(), Some("Car"));
+
+        let obj = Obj::parse(bytes).unwrap();
+        let mesh = obj.meshes()[1];
+        let parts: Vec<_> = mesh.submeshes_by_material().collect();
+        assert_eq!(parts.len(), 1);
+        assert!(matches!(parts[0].1, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn copy_mesh_handles() {
+        let obj = Obj::parse(b"v 0 0 0\no A\nf 1 1 1\no B\nf 1 1 1\n").unwrap();
+        let meshes = obj.meshes();
+
+        let handles: Vec<ObjMesh> = meshes.iter().chain(&meshes).copied().collect();
+        let selected = handles[1];
+        assert_eq!(handles.len(), 4);
+        assert_eq!(selected.name(), Some("B"));
+        assert_eq!(handles[3].name(), selected.name());
+    }
+
+    #[test]
+    fn display_name() {
+        let obj =
+            Obj::parse(b"v 0 0 0\nf 1 1 1\ng Group1 Group2\nf 1 1 1\no Object\nf 1 1 1\n").unwrap();
+        let names: Vec<_> = obj
+            .meshes()
+            .iter()
+            .map(|m| m.display_name().to_string())
+            .collect();
+        assert_eq!(names, ["unnamed", "Group1", "Object"]);
+        assert_eq!(obj.meshes()[1].name(), None);
+    }
+
+    #[test]
+    fn name_bytes() {
+        let obj = Obj::parse(b"v 0 0 0\no Cube\nusemtl Mat\nf 1 1 1\n").unwrap();
+        let mesh = obj.meshes()[0];
+        assert_eq!(mesh.name_bytes(), Some(&b"Cube"[..]));
+        assert_eq!(mesh.material_bytes(), Some(&b"Mat"[..]));
+    }
+
+    #[test]
+    fn approximate_normals() {
+        let obj = Obj::parse(format!("{CUBE_VERTICES}{}", CUBE_FACES.concat()).as_bytes()).unwrap();
+        let mesh = obj.meshes()[0];
+
+        assert_eq!(mesh.face_normals()[0], [0.0, 1.0, 0.0]);
+
+        let normals = mesh.approximate_normals();
+        assert_eq!(normals.len(), 8);
+        for (position, normal) in obj.vertices().iter().zip(normals) {
+            let expected = position.map(|c| c / 3f32.sqrt());
+            for (a, b) in normal.iter().zip(expected) {
+                assert!((a - b).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn owned_meshes() {
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\no A\nf 1 2 3\no B\nf 3 2 1\n").unwrap();
+        let meshes: Vec<_> = obj.clone().into_meshes().collect();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[1].as_mesh().name(), Some("B"));
+        assert_eq!(meshes[1].vertices(), obj.vertices());
+
+        let faces: Vec<_> = meshes.into_iter().map(|m| m.into_faces()).collect();
+        assert_eq!(faces[0], Faces::V(vec![vec![0, 1, 2]]));
+        assert_eq!(faces[1], Faces::V(vec![vec![2, 1, 0]]));
+    }
+
+    #[test]
+    fn triangle_count() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nv 2 2 0\n\
+            f 1 2 3\nf 1 2 4 3\nf 1 2 5 4 3\no Other\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(obj.meshes()[0].triangle_count(), 1 + 2 + 3);
+        assert_eq!(obj.meshes()[1].triangle_count(), 1);
+        assert_eq!(obj.total_triangle_count(), 7);
+        assert_eq!(obj.face_count(), 4);
+        #[cfg(feature = "trimesh")]
+        assert_eq!(obj.meshes()[0].triangulate().unwrap().0.0.len(), 6 * 3);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn weld_vertices() {
+        use super::Vertices;
+
+        // Every face has its own copy of the corners, with slightly different positions
+        let corners: Vec<&str> = CUBE_VERTICES.lines().collect();
+        let mut bytes = String::from("vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n");
+        for (f, face) in CUBE_FACES.iter().enumerate() {
+            for (i, v) in face[2..].split_whitespace().enumerate() {
+                let corner = corners[v.parse::<usize>().unwrap() - 1];
+                let [x, y, z] = [1, 2, 3].map(|c| {
+                    let value: f32 = corner.split_whitespace().nth(c).unwrap().parse().unwrap();
+                    value + f as f32 * 1e-5
+                });
+                bytes += &format!("v {x} {y} {z}\n");
+                bytes += &format!("vt {i} {i}\n");
+            }
+            let base = f * 4 + 1;
+            let t = f * 4 + 5;
+            bytes += &format!(
+                "f {}/{} {}/{} {}/{} {}/{}\n",
+                base,
+                t,
+                base + 1,
+                t + 1,
+                base + 2,
+                t + 2,
+                base + 3,
+                t + 3
+            );
+        }
+
+        let obj = Obj::parse(bytes.as_bytes()).unwrap();
+        let (mut indices, mut vertices) = obj.meshes()[0].triangulate().unwrap();
+        assert_eq!(vertices.positions.len(), 24);
+        let distinct: std::collections::HashSet<_> = vertices
+            .positions
+            .iter()
+            .map(|p| p.map(f32::to_bits))
+            .collect();
+        assert_eq!(distinct.len(), 24);
+
+        vertices.weld(&mut indices, 1e-3).unwrap();
+        assert_eq!(vertices.positions.len(), 8);
+        assert_eq!(vertices.uvs.as_ref().unwrap().len(), 8);
+        assert_eq!(indices.0.len(), 36);
+        assert!(indices.0.iter().all(|&i| i < 8));
+
+        // Points on both sides of a cell boundary
+        let mut vertices = Vertices {
+            positions: vec![[0.0099, 0.0, 0.0], [0.0101, 0.0, 0.0], [0.02, 0.0, 0.0]],
+            ..Default::default()
+        };
+        let mut indices = super::Indicies(vec![0, 1, 2]);
+        vertices.weld(&mut indices, 0.001).unwrap();
+        assert_eq!(vertices.positions, [[0.0099, 0.0, 0.0], [0.02, 0.0, 0.0]]);
+        assert_eq!(indices.0, [0, 0, 1]);
+
+        // Invalid input is rejected instead of panicking
+        let mut indices = super::Indicies(vec![0, 1, 2]);
+        assert!(vertices.weld(&mut indices, 0.001).is_err());
+        vertices.normals = Some(vec![[0.0, 0.0, 1.0]]);
+        indices.0 = vec![0, 1, 1];
+        assert!(vertices.weld(&mut indices, 0.001).is_err());
+        assert_eq!(vertices.positions.len(), 2);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn degenerate_triangles() {
+        use super::TriangulateOptions;
+
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 2 0 0\nv 0 1 0\nv 0.5 0.001 0\n\
+            f 1 1 2\nf 1 2 3\nf 1 2 4\nf 1 2 5\n",
+        )
+        .unwrap();
+        let mesh = obj.meshes()[0];
+
+        let (indices, _, degenerate) = mesh.triangulate_with(Default::default()).unwrap();
+        assert_eq!((indices.0.len(), degenerate), (12, 2));
+
+        let options = TriangulateOptions {
+            skip_degenerate: true,
+            epsilon: 0.001,
+            ..Default::default()
+        };
+        let (indices, vertices, degenerate) = mesh.triangulate_with(options).unwrap();
+        assert_eq!(degenerate, 3);
+        assert_eq!(indices.0, [0, 1, 2]);
+        assert_eq!(
+            vertices.positions,
+            [[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn fan_pivot() {
+        use crate::{FanPivot, TriangulateOptions};
+
+        // A rhombus where the diagonal from the first point is the long one
+        let obj = Obj::parse(b"v -10 0 0\nv 0 -1 0\nv 10 0 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        let mesh = obj.meshes()[0];
+        let worst_quality = |fan| {
+            let options = TriangulateOptions {
+                fan,
+                ..Default::default()
+            };
+            let (indices, vertices, _) = mesh.triangulate_with(options).unwrap();
+            let p = |i: usize| vertices.positions[i];
+            indices
+                .0
+                .chunks(3)
+                .map(|t| super::triangle_quality(p(t[0]), p(t[1]), p(t[2])))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        let first = worst_quality(FanPivot::First);
+        let best = worst_quality(FanPivot::BestAspect);
+        assert!(best > first * 2.0, "{best} <= {first}");
+        assert_eq!(
+            mesh.triangulate_with(Default::default()).unwrap().0.0.len(),
+            6
+        );
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn triangle_strips() {
+        use crate::{Topology, TriangulateOptions};
+
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+            f 1 2 3 4\nf 2 5 6 3\nf 2 5 6\n",
+        )
+        .unwrap();
+        let options = TriangulateOptions {
+            topology: Topology::Strip,
+            ..Default::default()
+        };
+        let (indices, vertices, sources) =
+            obj.meshes()[0].triangulate_with_source(options).unwrap();
+        let positions: Vec<_> = indices.0.iter().map(|i| vertices.positions[*i]).collect();
+
+        // Quads zigzag as 0, 1, 3, 2 and faces are joined by repeated indices
+        let v = |x, y| [x, y, 0.0];
+        assert_eq!(
+            positions,
+            [
+                v(0.0, 0.0),
+                v(1.0, 0.0),
+                v(0.0, 1.0),
+                v(1.0, 1.0),
+                v(1.0, 1.0),
+                v(1.0, 0.0),
+                v(1.0, 0.0),
+                v(2.0, 0.0),
+                v(1.0, 1.0),
+                v(2.0, 1.0),
+                v(2.0, 1.0),
+                v(1.0, 0.0),
+                v(1.0, 0.0),
+                v(2.0, 0.0),
+                v(2.0, 1.0),
+            ]
+        );
+        assert_eq!(sources.len(), indices.0.len() - 2);
+        assert_eq!(sources, [0, 0, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn triangle_sources() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\n\
+            f 1 2 3 4\nf 2 5 3\nf 1 1 2\nf 4 3 5 1 2\n",
+        )
+        .unwrap();
+        let mesh = obj.meshes()[0];
+
+        let (indices, _, sources) = mesh.triangulate_with_source(Default::default()).unwrap();
+        assert_eq!(sources, [0, 0, 1, 2, 3, 3, 3]);
+        assert_eq!(indices.0.len(), sources.len() * 3);
+
+        let options = crate::TriangulateOptions {
+            skip_degenerate: true,
+            ..Default::default()
+        };
+        let (indices, _, sources) = mesh.triangulate_with_source(options).unwrap();
+        assert_eq!(sources, [0, 0, 1, 3, 3, 3]);
+        assert_eq!(indices.0.len(), sources.len() * 3);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn triangulate_secondary_uvs() {
+        // Points differing only in their secondary uv are not shared
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 1\nvn 0 0 1\n\
+            f 1/1/1/1 2/1/1/1 3/1/1/2\nf 1/1/1/2 3/1/1/2 4/1/1/2\n",
+        )
+        .unwrap();
+        let (indices, vertices) = obj.meshes()[0].triangulate().unwrap();
+        assert_eq!(indices.0, [0, 1, 2, 3, 2, 4]);
+        assert_eq!(
+            vertices.uvs2.unwrap(),
+            [[0.0, 0.0], [0.0, 0.0], [1.0, 1.0], [1.0, 1.0], [1.0, 1.0]]
+        );
+        assert_eq!(vertices.uvs.unwrap().len(), 5);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn triangulate_borrowed() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 1\nvn 0 0 1\nvn 0 0 1\n\
+            o A\nf 1//1 2//2 3//3 4//4\no B\nf 2//2 3//3 4//4\no C\nf 1//1 2//2 3//3\nf 4//1 3//3 2//2\n",
+        )
+        .unwrap();
+        for mesh in obj.meshes() {
+            let (indices, vertices) = mesh.triangulate_borrowed().unwrap();
+            assert_eq!(
+                (indices, vertices.clone().into_owned()),
+                mesh.triangulate().unwrap()
+            );
+            // Mesh C reuses vertex 4 with another normal
+            assert_eq!(vertices.is_borrowed(), mesh.name() != Some("C"));
+        }
+        let (indices, vertices) = obj.meshes()[1].triangulate_borrowed().unwrap();
+        assert_eq!(indices.0, [0, 1, 2]);
+        assert_eq!(vertices.positions, &obj.vertices()[1..]);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn polygons() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\nvn 0 0 1\n\
+            f 1//1 2//1 3//1 4//1\nf 2//1 5//1 6//1\nf 1//1 2//1 5//1 6//1 4//1\n",
+        )
+        .unwrap();
+        let mesh = obj.meshes()[0];
+        let (indices, vertices, counts) = mesh.polygons().unwrap();
+        assert_eq!(counts, [4, 3, 5]);
+        assert_eq!(indices.0, [0, 1, 2, 3, 1, 4, 5, 0, 1, 4, 5, 3]);
+        assert_eq!(vertices.positions.len(), 6);
+        assert_eq!(vertices.normals.unwrap().len(), 6);
+
+        // Same vertices as the triangulated mesh
+        let (_, triangulated) = mesh.triangulate().unwrap();
+        assert_eq!(triangulated.positions, vertices.positions);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn triangulate_adjacency() {
+        // The second triangle uses another uv on the shared edge
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 1\n\
+            f 1/1 2/1 3/1\nf 1/2 3/1 4/1\n",
+        )
+        .unwrap();
+        let (indices, vertices) = obj.meshes()[0].triangulate_adjacency().unwrap();
+        assert_eq!(vertices.positions.len(), 5);
+        assert_eq!(indices.0, [0, 2, 1, 0, 2, 4, 3, 1, 2, 3, 4, 2]);
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn indexed_mesh() {
+        // Two quads sharing an edge with a uv seam along it
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\nv 9 9 9\n\
+            vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvt 0 0\nvt 0 1\nvn 0 0 1\n\
+            f 1/1/1 2/2/1 3/3/1 4/4/1\nf 2/5/1 5/2/1 6/3/1 3/6/1\n",
+        )
+        .unwrap();
+        let mesh = obj.meshes()[0].indexed().unwrap();
+
+        // The unused vertex is dropped and no position is duplicated
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.uvs.len(), 6);
+        assert_eq!(mesh.normals, [[0.0, 0.0, 1.0]]);
+        assert_eq!(mesh.pos_idx, [0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2]);
+        assert_eq!(mesh.uv_idx, [0, 1, 2, 0, 2, 3, 4, 1, 2, 4, 2, 5]);
+        assert_eq!(mesh.normal_idx, [0; 12]);
+
+        let (_, vertices) = obj.meshes()[0].triangulate().unwrap();
+        assert_eq!(vertices.positions.len(), 8);
+
+        let positions = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let mesh = positions.meshes()[0].indexed().unwrap();
+        assert_eq!(mesh.pos_idx, [0, 1, 2]);
+        assert!(mesh.uv_idx.is_empty() && mesh.normal_idx.is_empty());
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn interleaved_vertices() {
+        use super::Vertex;
+
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5 1\nf 1/1 2/1 3/1\n").unwrap();
+        let vertices = obj.meshes()[0].triangulate().unwrap().1.interleaved();
+
+        assert_eq!(std::mem::size_of::<Vertex>(), 32);
+        assert_eq!(std::mem::offset_of!(Vertex, normal), 12);
+        assert_eq!(std::mem::offset_of!(Vertex, uv), 24);
+        assert_eq!(
+            vertices[1],
+            Vertex {
+                position: [1.0, 0.0, 0.0],
+                normal: Vertex::DEFAULT_NORMAL,
+                uv: [0.5, 1.0],
+            }
+        );
+
+        #[cfg(feature = "bytemuck")]
+        assert_eq!(bytemuck::cast_slice::<_, u8>(&vertices).len(), 3 * 32);
+    }
+
+    #[test]
+    fn face_indices() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvt 1 1\nvn 0 0 1\n\
+            f 1/1/1 2/2/1 3/1/1\nf 2/1/1 4/2/1 3/2/1\n",
+        )
+        .unwrap();
+        let mesh = &obj.meshes()[0];
+
+        assert_eq!(mesh.face_indices_u32().unwrap(), [[0, 1, 2], [1, 3, 2]]);
+        let uvs = mesh.face_uv_indices().unwrap().unwrap();
+        assert_eq!(uvs, [[0, 1, 0], [0, 1, 1]]);
+        let normals = mesh.face_normal_indices().unwrap().unwrap();
+        assert_eq!(normals, [[0, 0, 0], [0, 0, 0]]);
+
+        // Indices beyond u32 are reported instead of truncated
+        let data = VertexData::default();
+        let groups = GroupNames::default();
+        let mesh = MeshData {
+            faces: Some(Faces::VN(vec![vec![(0, u32::MAX as usize + 1)]])),
+            ..Default::default()
+        };
+        let mesh = ObjMesh::new(&data, &groups, &mesh);
+        assert_eq!(mesh.face_indices_u32().unwrap(), [[0]]);
+        assert!(mesh.face_normal_indices().is_err());
+    }
+
+    #[test]
+    fn front_face_detection() {
+        let winding = |faces: &[String]| {
+            let obj = Obj::parse(format!("{CUBE_VERTICES}{}", faces.concat()).as_bytes()).unwrap();
+            obj.meshes()[0].detect_front_face()
+        };
+
+        let ccw: Vec<String> = CUBE_FACES.iter().map(|f| f.to_string()).collect();
+        assert_eq!(winding(&ccw), Winding::CounterClockwise);
+
+        let cw: Vec<String> = CUBE_FACES
+            .iter()
+            .map(|f| {
+                let mut points: Vec<_> = f.trim()[2..].split(' ').collect();
+                points.reverse();
+                format!("f {}\n", points.join(" "))
+            })
+            .collect();
+        assert_eq!(winding(&cw), Winding::Clockwise);
+
+        assert_eq!(winding(&ccw[1..]), Winding::Unknown);
+    }
 }
@@ -1,35 +1,731 @@
+mod events;
+mod freeform;
+mod hierarchy;
 mod mesh;
 mod parser;
+#[cfg(feature = "trimesh")]
+mod simplify;
+mod transform;
+mod validate;
 
+pub use events::*;
+pub use freeform::*;
+pub use hierarchy::*;
 pub use mesh::*;
+pub use transform::*;
+pub use validate::*;
+
+use parser::{Hooks, Includes};
+
+use core::num::NonZero;
 
 use winnow::{BStr, Parser};
 
-use crate::WobjError;
+#[cfg(feature = "std")]
+use crate::Mtl;
+use crate::compat::*;
+use crate::math;
+use crate::warning::Warnings;
+use crate::{ParseOptions, Warning, WobjError};
 
 /// Wavefont OBJ data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Obj {
     data: VertexData,
+    groups: GroupNames,
     meshes: Vec<MeshData>,
+    comments: Vec<Comment>,
+    shadow_obj: Option<String>,
+    trace_obj: Option<String>,
+}
+
+/// Comment line of an OBJ file
+///
+/// Only captured with [`ParseOptions::capture_comments`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// 1-based line number of the comment
+    pub line: usize,
+    /// Text after the `#`, without surrounding whitespace
+    pub text: String,
 }
 
+/// Interned group names, indexed by group id
+type GroupNames = Set<String>;
+
 impl Obj {
     /// Parses OBJ file data
+    ///
+    /// Any input is safe to parse: malformed or truncated data is reported as
+    /// an error and never panics. The `fuzz` directory has fuzz targets for it.
     pub fn parse(bytes: &[u8]) -> Result<Self, WobjError> {
-        parser::parse_obj
+        Self::parse_with(bytes, ParseOptions::default())
+    }
+
+    /// Parses OBJ file data with the specified options
+    pub fn parse_with(bytes: &[u8], options: ParseOptions) -> Result<Self, WobjError> {
+        Self::parse_inner(bytes, &options, Hooks::default())
+    }
+
+    /// Parses OBJ text
+    ///
+    /// Names and paths can not be invalid UTF-8, so they are not validated
+    /// and [`ParseOptions::on_invalid_utf8`] never applies.
+    ///
+    /// ```
+    /// let obj = wobj::Obj::parse_str(include_str!("../../assets/cube.obj"))?;
+    /// assert_eq!(obj.meshes()[0].name(), Some("Cube"));
+    /// # Ok::<(), wobj::WobjError>(())
+    /// ```
+    pub fn parse_str(text: &str) -> Result<Self, WobjError> {
+        let hooks = Hooks {
+            valid_utf8: true,
+            ..Default::default()
+        };
+        Self::parse_inner(text.as_bytes(), &ParseOptions::default(), hooks)
+    }
+
+    /// Parses OBJ file data, inlining the files referenced by `call` statements
+    ///
+    /// The loader receives the path as written and returns the file data.
+    /// Arguments after the path are ignored, `$1`-style substitutions in the
+    /// called file are not supported. Indices in a called file refer to its
+    /// own vertex data.
+    /// Called files may call further files, but not form a cycle.
+    pub fn parse_with_loader<L>(
+        bytes: &[u8],
+        options: ParseOptions,
+        mut loader: L,
+    ) -> Result<Self, WobjError>
+    where
+        L: FnMut(&str) -> Result<Vec<u8>, WobjError>,
+    {
+        let mut includes = Includes::new(Some(&mut loader));
+        let hooks = Hooks {
+            includes: Some(&mut includes),
+            ..Default::default()
+        };
+        Self::parse_inner(bytes, &options, hooks)
+    }
+
+    /// Parses OBJ file data, reporting the progress to `progress`
+    ///
+    /// The callback receives the number of bytes consumed and the total, every
+    /// few thousand statements and once parsing is done. Useful for updating a
+    /// loading bar while parsing large files.
+    pub fn parse_with_progress<P>(
+        bytes: &[u8],
+        options: ParseOptions,
+        mut progress: P,
+    ) -> Result<Self, WobjError>
+    where
+        P: FnMut(usize, usize),
+    {
+        let hooks = Hooks {
+            progress: Some(&mut progress),
+            ..Default::default()
+        };
+        Self::parse_inner(bytes, &options, hooks)
+    }
+
+    /// Parses OBJ file data, keeping only the objects accepted by `filter`
+    ///
+    /// The filter receives the name of each object, `None` for elements before
+    /// the first `o` statement. Faces and free-form elements of rejected objects
+    /// are skipped without being parsed and no mesh is created for them, but the
+    /// vertex data is always kept since any element may refer to it. Useful for
+    /// previewing one part of a large file.
+    pub fn parse_filtered<F>(
+        bytes: &[u8],
+        options: ParseOptions,
+        mut filter: F,
+    ) -> Result<Self, WobjError>
+    where
+        F: FnMut(Option<&str>) -> bool,
+    {
+        let hooks = Hooks {
+            filter: Some(&mut filter),
+            ..Default::default()
+        };
+        Self::parse_inner(bytes, &options, hooks)
+    }
+
+    /// Parses OBJ file data with any combination of hooks, which all public
+    /// parse functions go through
+    fn parse_inner(
+        bytes: &[u8],
+        options: &ParseOptions,
+        mut hooks: Hooks,
+    ) -> Result<Self, WobjError> {
+        (|input: &mut &BStr| parser::parse_obj(input, options, &mut hooks))
             .parse(BStr::new(bytes))
             .map_err(WobjError::from)
     }
 
+    #[cfg(feature = "std")]
+    /// Parses OBJ file data, measuring the parse time and input size
+    ///
+    /// For tracking parser performance, see [`ParseStats::throughput`].
+    pub fn parse_with_stats(
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(Self, ParseStats), WobjError> {
+        let mut lines = 0;
+        let start = std::time::Instant::now();
+        let hooks = Hooks {
+            lines: Some(&mut lines),
+            ..Default::default()
+        };
+        let obj = Self::parse_inner(bytes, &options, hooks)?;
+        let duration = start.elapsed();
+
+        let stats = ParseStats {
+            bytes: bytes.len(),
+            lines,
+            vertices: obj.data.vertex.len(),
+            faces: obj.face_count(),
+            duration,
+        };
+        Ok((obj, stats))
+    }
+
+    #[cfg(feature = "memmap")]
+    /// Memory-maps and parses an OBJ file
+    ///
+    /// Avoids reading large files into memory first. The map only lives until
+    /// parsing is done, the returned data does not borrow from it.
+    ///
+    /// Failing to open or map the file returns an error with
+    /// [`WobjError::io_error`] set.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by another process while it
+    /// is being parsed. Memory maps can not prevent that, and a file changing
+    /// under the parser is undefined behavior.
+    pub unsafe fn parse_mmap<P: AsRef<std::path::Path>>(path: P) -> Result<Self, WobjError> {
+        let path = path.as_ref();
+        let map = std::fs::File::open(path)
+            // SAFETY: upheld by the caller, see above
+            .and_then(|file| unsafe { memmap2::Mmap::map(&file) })
+            .map_err(|e| {
+                WobjError::from(e)
+                    .map_message(|m| format!("failed to map '{}': {m}", path.display()))
+            })?;
+        Self::parse(&map)
+    }
+
+    /// Parses OBJ file data, skipping statements which fail to parse
+    ///
+    /// Returns the errors of the skipped statements prefixed with their line
+    /// number. Useful for batch processing files which are usable except for
+    /// a few broken lines.
+    ///
+    /// A skipped `v`, `vn`, `vt` or `vp` statement still takes its place as
+    /// an element filled with zeros, so the indices of later faces stay
+    /// aligned. Faces using such an element are skipped with an error too.
+    pub fn parse_lossy(
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<WobjError>), WobjError> {
+        let mut errors = Vec::new();
+        let hooks = Hooks {
+            errors: Some(&mut errors),
+            ..Default::default()
+        };
+        let obj = Self::parse_inner(bytes, &options, hooks)?;
+
+        Ok((obj, errors))
+    }
+
+    /// Parses a chunk of OBJ data whose indices continue a global numbering
+    ///
+    /// The chunk is parsed as if it was preceded by the specified number of
+    /// vertices, normals and uvs, for streaming a file in parts. Resolved
+    /// indices point into the data of all chunks, where the elements of this
+    /// chunk start at the offsets, so they are out of range for the chunk
+    /// alone.
+    ///
+    /// Fails if the offsets leave no room for the elements of the chunk.
+    pub fn parse_with_offset(
+        bytes: &[u8],
+        options: ParseOptions,
+        vertex_offset: usize,
+        normal_offset: usize,
+        texture_offset: usize,
+    ) -> Result<Self, WobjError> {
+        let offset = Counts {
+            vertex: vertex_offset,
+            normal: normal_offset,
+            texture: texture_offset,
+            param: 0,
+        };
+        // A chunk has fewer elements of each kind than bytes, so the indices
+        // can not overflow if this does not
+        let most = Counts {
+            vertex: bytes.len(),
+            normal: bytes.len(),
+            texture: bytes.len(),
+            param: bytes.len(),
+        };
+        if offset.checked_add(most).is_none() {
+            return Err("vertex data offset is too large".into());
+        }
+
+        let hooks = Hooks {
+            offset,
+            ..Default::default()
+        };
+        Self::parse_inner(bytes, &options, hooks)
+    }
+
+    /// Parses OBJ file data keeping the face indices as written
+    ///
+    /// See [`ObjMesh::raw_faces`].
+    pub fn parse_raw_indices(bytes: &[u8]) -> Result<Self, WobjError> {
+        let options = ParseOptions {
+            raw_indices: true,
+            ..Default::default()
+        };
+        Self::parse_with(bytes, options)
+    }
+
+    /// Parses OBJ file data while collecting warnings about skipped or suspicious data
+    pub fn parse_verbose(
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Warning>), WobjError> {
+        let mut list = Vec::new();
+        let hooks = Hooks {
+            warnings: Some(Warnings::new(bytes, &mut list)),
+            ..Default::default()
+        };
+        let obj = Self::parse_inner(bytes, &options, hooks)?;
+
+        Ok((obj, list))
+    }
+
     /// List of all mesh objects
     pub fn meshes<'obj>(&'obj self) -> Vec<ObjMesh<'obj>> {
         self.meshes
             .iter()
-            .map(|m| ObjMesh::new(&self.data, m))
+            .map(|m| ObjMesh::new(&self.data, &self.groups, m))
             .collect()
     }
 
+    /// Consumes the OBJ, returning the mesh objects with owned faces
+    pub fn into_meshes(self) -> impl Iterator<Item = OwnedMesh> {
+        let data = Arc::new(self.data);
+        let groups = Arc::new(self.groups);
+        let meshes = self.meshes.into_iter();
+        meshes.map(move |mesh| OwnedMesh::new(data.clone(), groups.clone(), mesh))
+    }
+
+    /// Total number of faces in all meshes
+    pub fn face_count(&self) -> usize {
+        let faces = self.meshes.iter().filter_map(|m| m.faces.as_ref());
+        faces.map(Faces::len).sum()
+    }
+
+    /// Total number of triangles in all meshes
+    ///
+    /// See [`ObjMesh::triangle_count`].
+    pub fn total_triangle_count(&self) -> usize {
+        self.meshes().iter().map(ObjMesh::triangle_count).sum()
+    }
+
+    /// Element counts of the OBJ
+    pub fn summary(&self) -> ObjSummary {
+        let materials = self.meshes.iter().flat_map(|m| {
+            let changes = m.material_changes.iter().map(|(_, material)| material);
+            core::iter::once(&m.material).chain(changes).flatten()
+        });
+        let mtllibs = self.meshes.iter().filter_map(|m| m.mtllib.as_ref());
+
+        ObjSummary {
+            vertices: self.data.vertex.len(),
+            normals: self.data.normal.len(),
+            uvs: self.data.texture.len(),
+            meshes: self.meshes.len(),
+            faces: self.face_count(),
+            triangles: self.total_triangle_count(),
+            materials: materials.collect::<Set<_>>().len(),
+            mtllibs: mtllibs.collect::<Set<_>>().len(),
+        }
+    }
+
+    /// Mesh object at the specified position, in file order
+    pub fn mesh(&self, index: usize) -> Option<ObjMesh<'_>> {
+        let mesh = self.meshes.get(index)?;
+        Some(ObjMesh::new(&self.data, &self.groups, mesh))
+    }
+
+    /// Number of mesh objects
+    pub fn mesh_count(&self) -> usize {
+        self.meshes.len()
+    }
+
+    /// First mesh object with the specified name
+    ///
+    /// The name is matched exactly, including case.
+    pub fn mesh_by_name(&self, name: &str) -> Option<ObjMesh<'_>> {
+        self.meshes
+            .iter()
+            .find(|m| m.name.as_deref() == Some(name))
+            .map(|m| ObjMesh::new(&self.data, &self.groups, m))
+    }
+
+    /// Mesh objects which are part of the specified group
+    ///
+    /// The group name is matched exactly, including case.
+    pub fn meshes_in_group(&self, group: &str) -> impl Iterator<Item = ObjMesh<'_>> {
+        let id = self.groups.get_index_of(group).map(|id| id as u32);
+        self.meshes
+            .iter()
+            .filter(move |m| id.is_some_and(|id| m.groups.contains(&id)))
+            .map(|m| ObjMesh::new(&self.data, &self.groups, m))
+    }
+
+    /// Name of the group with the specified id
+    ///
+    /// See [`ObjMesh::groups`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id does not belong to this OBJ.
+    pub fn group_name(&self, id: u32) -> &str {
+        &self.groups[id as usize]
+    }
+
+    #[cfg(feature = "std")]
+    /// Loads the material libraries used by the meshes
+    ///
+    /// Each `mtllib` is looked up relative to the search paths in order and
+    /// the first existing file is used. The libraries are keyed by their path
+    /// as written in the OBJ.
+    pub fn load_materials(
+        &self,
+        search_paths: &[std::path::PathBuf],
+    ) -> Result<Map<String, Mtl>, WobjError> {
+        let mut mtls = Map::default();
+
+        for mtllib in self.meshes.iter().filter_map(|m| m.mtllib.as_ref()) {
+            if mtls.contains_key(mtllib) {
+                continue;
+            }
+
+            let candidates = search_paths.iter().map(|dir| dir.join(mtllib));
+            let Some(path) = candidates.clone().find(|path| path.is_file()) else {
+                let tried: Vec<_> = candidates.map(|p| p.display().to_string()).collect();
+                return Err(WobjError::from(format!(
+                    "material library '{mtllib}' not found (tried: {})",
+                    tried.join(", ")
+                )));
+            };
+
+            let mtl = Mtl::parse_path(path)?;
+            mtls.insert(mtllib.clone(), mtl);
+        }
+
+        Ok(mtls)
+    }
+
+    /// Collapses all meshes into a single mesh, discarding names, groups and materials
+    ///
+    /// If the meshes have different face formats, all faces are promoted to the
+    /// richest one. Faces without uvs then point to an appended default uv
+    /// `[0, 0]`, and faces without normals get an appended flat normal, `[0, 0, 1]`
+    /// for degenerate faces. Secondary uvs are dropped.
+    pub fn into_single_mesh(mut self) -> Self {
+        let mut free_forms = Vec::new();
+        let faces: Vec<Faces> = self
+            .meshes
+            .drain(..)
+            .filter_map(|m| {
+                free_forms.extend(m.free_forms);
+                m.faces
+            })
+            .collect();
+        if faces.is_empty() {
+            if !free_forms.is_empty() {
+                self.meshes.push(MeshData {
+                    free_forms,
+                    ..Default::default()
+                });
+            }
+            return self;
+        }
+
+        let uv = faces.iter().any(Faces::has_uvs).then(|| {
+            if !faces.iter().all(Faces::has_uvs) {
+                self.data.texture.push([0.0, 0.0]);
+                if !self.data.texture_w.is_empty() {
+                    self.data.texture_w.push(0.0);
+                }
+            }
+            self.data.texture.len().saturating_sub(1)
+        });
+        let has_normals = faces.iter().any(Faces::has_normals);
+
+        let positions = &self.data.vertex;
+        let mut flat_normals = Vec::new();
+        let points: Vec<Vec<_>> = (faces.iter().flat_map(Faces::iter_faces))
+            .map(|face| {
+                let mut face: Vec<_> = face.collect();
+                if has_normals && face[0].n.is_none() {
+                    let n = self.data.normal.len() + flat_normals.len();
+                    flat_normals.push(flat_normal(positions, &face));
+                    face.iter_mut().for_each(|point| point.n = Some(n));
+                }
+                face
+            })
+            .collect();
+        self.data.normal.extend(flat_normals);
+
+        // Every point has a normal by now, so the default is never used
+        let normal = has_normals.then_some(0);
+        let points = points.into_iter().map(Vec::into_iter);
+        self.meshes.push(MeshData {
+            faces: Some(Faces::from_points(points, uv, normal)),
+            free_forms,
+            ..Default::default()
+        });
+
+        self
+    }
+
+    /// Appends the meshes and vertex data of another OBJ
+    ///
+    /// The face indices of `other` are shifted past the existing vertex data,
+    /// so both files must share a coordinate system. Raw faces are kept as
+    /// written.
+    ///
+    /// Fails if the combined group ids do not fit in a `u32`.
+    pub fn merge(&mut self, other: Obj) -> Result<(), WobjError> {
+        // Intern the group names first, so a failure leaves the meshes and data unchanged
+        let ids = other
+            .groups
+            .iter()
+            .map(|name| parser::intern(&mut self.groups, name))
+            .collect::<Result<Vec<_>, _>>()?;
+        for mut mesh in other.meshes {
+            mesh.offset(self.data.counts());
+            mesh.groups = mesh.groups.iter().map(|&id| ids[id as usize]).collect();
+            self.meshes.push(mesh);
+        }
+        self.data.append(other.data);
+        self.comments.extend(other.comments);
+        self.shadow_obj = self.shadow_obj.take().or(other.shadow_obj);
+        self.trace_obj = self.trace_obj.take().or(other.trace_obj);
+        Ok(())
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Create a single triangulated mesh from the faces of all meshes
+    ///
+    /// Points shared between meshes are deduplicated. If only some meshes have
+    /// uvs, the others get the uv `[0, 0]`. If only some have normals, smooth
+    /// normals are generated for the others from their triangles.
+    pub fn triangulate_all(&self) -> Result<(Indicies, Vertices), WobjError> {
+        let mut points = Set::default();
+        let mut indices = Vec::new();
+        for mesh in self.meshes() {
+            let options = mesh::TriangulateOptions::default();
+            indices.extend(mesh.triangulate_points(options, None, &mut points).0);
+        }
+
+        let missing_normals: Vec<bool> = points.iter().map(|(p, _)| p.n.is_none()).collect();
+        let mut vertices = mesh::point_vertices(&self.data, points)?;
+        if let Some(normals) = &mut vertices.normals {
+            let smooth = smooth_normals(&vertices.positions, &indices);
+            for ((normal, smooth), missing) in normals.iter_mut().zip(smooth).zip(missing_normals) {
+                if missing {
+                    *normal = smooth;
+                }
+            }
+        }
+
+        Ok((Indicies(indices), vertices))
+    }
+
+    #[cfg(feature = "trimesh")]
+    /// Triangulates all meshes into interleaved vertices and `u32` indices
+    ///
+    /// The shape most renderers upload. Vertices get
+    /// [`Vertex::DEFAULT_NORMAL`] if no faces have normals and the uv `[0, 0]`
+    /// if they have no uvs, like [`Vertices::interleaved`]. Meshes
+    /// without normals next to meshes with normals get generated ones, see
+    /// [`Obj::triangulate_all`].
+    pub fn to_interleaved(&self) -> Result<(Vec<Vertex>, Vec<u32>), WobjError> {
+        let (indices, vertices) = self.triangulate_all()?;
+        let interleaved = vertices.interleaved();
+        let indices = indices
+            .0
+            .into_iter()
+            .map(u32::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(|_| "too many vertices for u32 indices")?;
+        Ok((interleaved, indices))
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Triangulate each mesh in parallel
+    ///
+    /// Produces the same results as calling [`ObjMesh::triangulate`] on every
+    /// mesh. The work is split per mesh, so a file with a single large mesh
+    /// is still triangulated on one thread. `cargo bench --features rayon`
+    /// compares it with sequential triangulation.
+    pub fn par_triangulate(&self) -> Vec<Result<(Indicies, Vertices), WobjError>> {
+        use rayon::prelude::*;
+        self.meshes().par_iter().map(ObjMesh::triangulate).collect()
+    }
+
+    /// Generates smooth vertex normals for the faces without normals
+    ///
+    /// One normal is appended for each vertex position used by those faces,
+    /// averaged from the weighted normals of the faces using it. Faces are
+    /// upgraded from [`Faces::V`] to [`Faces::VN`] and from [`Faces::VT`] to
+    /// [`Faces::VTN`], faces which already have normals are left unchanged.
+    /// Does nothing if all faces have normals.
+    pub fn generate_normals(&mut self, weighting: NormalWeighting) {
+        let faces = self.meshes.iter().filter_map(|m| m.faces.as_ref());
+        let without_normals: Vec<&Faces> = faces.filter(|f| !f.has_normals()).collect();
+        if without_normals.is_empty() {
+            return;
+        }
+
+        let mut normals = vec![[0.0; 3]; self.data.vertex.len()];
+        let mut used = vec![false; self.data.vertex.len()];
+        for faces in without_normals {
+            mesh::accumulate_normals(&self.data.vertex, faces, weighting, &mut normals);
+            for point in faces.iter_faces().flatten() {
+                if let Some(used) = used.get_mut(point.v) {
+                    *used = true;
+                }
+            }
+        }
+
+        // Normal index of each used position, out of range ones stay out of range
+        let mut index = vec![usize::MAX; self.data.vertex.len()];
+        for (v, normal) in normals.into_iter().enumerate().filter(|&(v, _)| used[v]) {
+            index[v] = self.data.normal.len();
+            self.data.normal.push(crate::math::normalize(normal));
+        }
+        let normal_of = |v: usize| index.get(v).copied().unwrap_or(usize::MAX);
+
+        for mesh in &mut self.meshes {
+            mesh.faces = mesh.faces.take().map(|faces| faces.with_normals(normal_of));
+        }
+    }
+
+    /// Generates vertex normals for the faces without normals, keeping sharp creases
+    ///
+    /// The normals are smoothed across all meshes and between vertices at
+    /// the same position, so a surface split into several objects gets no
+    /// seam even if each object has its own copy of the edge vertices. Faces
+    /// meeting at an angle above `angle_threshold` radians are not smoothed
+    /// together, smoothing groups are ignored. Faces are upgraded like in
+    /// [`Obj::generate_normals`].
+    pub fn generate_crease_normals(&mut self, weighting: NormalWeighting, angle_threshold: f32) {
+        // Duplicated vertices are welded, positions are compared exactly
+        let mut welded_positions: Map<[u32; 3], usize> = Map::default();
+        let welded: Vec<usize> = self
+            .data
+            .vertex
+            .iter()
+            .map(|p| {
+                let next = welded_positions.len();
+                // Adding 0 turns -0 into 0
+                let key = p.map(|c| (c + 0.0).to_bits());
+                *welded_positions.entry(key).or_insert(next)
+            })
+            .collect();
+
+        // Vertex, unit face normal and weighted normal of each face corner
+        let mut corners = Vec::new();
+        let mut at_vertex = vec![Vec::new(); welded_positions.len()];
+        let faces = self.meshes.iter().filter_map(|m| m.faces.as_ref());
+        for faces in faces.filter(|f| !f.has_normals()) {
+            for face in faces.iter_faces() {
+                let face: Vec<usize> = face.map(|p| p.v).collect();
+                let positions: Option<Vec<_>> = face
+                    .iter()
+                    .map(|v| self.data.vertex.get(*v).copied())
+                    .collect();
+                let Some(positions) = positions else {
+                    // Out of range faces get a zero normal
+                    corners.extend(face.iter().map(|&v| (v, [0.0; 3], [0.0; 3])));
+                    continue;
+                };
+                let (unit, weighted) = mesh::corner_normals(&positions, weighting);
+                for (&v, weighted) in face.iter().zip(weighted) {
+                    at_vertex[welded[v]].push(corners.len());
+                    corners.push((v, unit, weighted));
+                }
+            }
+        }
+
+        // Smooth each corner with the faces around its vertex within the threshold
+        let mut unique: Map<(usize, [u32; 3]), usize> = Map::default();
+        let mut indices = Vec::with_capacity(corners.len());
+        for (i, &(v, unit, weighted)) in corners.iter().enumerate() {
+            let around = welded.get(v).map(|&w| &at_vertex[w]);
+            let others = around.into_iter().flatten().filter(|&&c| c != i);
+            let normal = others
+                .map(|&c| corners[c])
+                .filter(|c| crate::math::angle(unit, c.1) <= angle_threshold)
+                .fold(weighted, |sum, c| crate::math::add(sum, c.2));
+            let normal = crate::math::normalize(normal);
+            let index = unique
+                .entry((v, normal.map(f32::to_bits)))
+                .or_insert_with(|| {
+                    self.data.normal.push(normal);
+                    self.data.normal.len() - 1
+                });
+            indices.push(*index);
+        }
+
+        let mut indices = indices.into_iter();
+        for faces in self.meshes.iter_mut().filter_map(|m| m.faces.as_mut()) {
+            if faces.has_normals() {
+                continue;
+            }
+            let points: Vec<Vec<_>> = faces
+                .iter_faces()
+                .map(|face| {
+                    let point = |p: FacePoint<usize>| FacePoint {
+                        n: indices.next(),
+                        ..p
+                    };
+                    face.map(point).collect()
+                })
+                .collect();
+            let uv = faces.has_uvs().then_some(0);
+            *faces = Faces::from_points(points.into_iter().map(Vec::into_iter), uv, Some(0));
+        }
+    }
+
+    /// Comments of the file, if they were captured
+    ///
+    /// Comments of files inlined by `call` statements are not included.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// (shadow_obj) file used for casting shadows instead of this geometry
+    ///
+    /// Normalized like [`ObjMesh::mtllib`].
+    pub fn shadow_obj(&self) -> Option<&str> {
+        self.shadow_obj.as_deref()
+    }
+
+    /// (trace_obj) file used for ray tracing instead of this geometry
+    ///
+    /// Normalized like [`ObjMesh::mtllib`].
+    pub fn trace_obj(&self) -> Option<&str> {
+        self.trace_obj.as_deref()
+    }
+
     /// All vertex positions
     pub fn vertices(&self) -> &[[f32; 3]] {
         &self.data.vertex
@@ -44,6 +740,132 @@ impl Obj {
     pub fn uvs(&self) -> &[[f32; 2]] {
         &self.data.texture
     }
+
+    /// Third `w` coordinate of all vertex uvs, parallel to [`Obj::uvs`]
+    ///
+    /// Empty unless parsed with [`ParseOptions::uv_w`]. Missing values are 0.
+    pub fn uvs_w(&self) -> &[f32] {
+        &self.data.texture_w
+    }
+
+    /// All vertex positions as contiguous `x y z` floats
+    ///
+    /// Arrays are laid out without padding, so this is a view of the same
+    /// memory as [`Obj::vertices`] and can be passed to APIs taking a float
+    /// buffer without copying.
+    pub fn vertices_flat(&self) -> &[f32] {
+        self.data.vertex.as_flattened()
+    }
+
+    /// All vertex normals as contiguous `x y z` floats
+    ///
+    /// See [`Obj::vertices_flat`].
+    pub fn normals_flat(&self) -> &[f32] {
+        self.data.normal.as_flattened()
+    }
+
+    /// All vertex uvs as contiguous `u v` floats
+    ///
+    /// See [`Obj::vertices_flat`].
+    pub fn uvs_flat(&self) -> &[f32] {
+        self.data.texture.as_flattened()
+    }
+
+    /// All vertex positions for in-place modification
+    ///
+    /// Only the values can be changed, so face indices stay valid.
+    pub fn vertices_mut(&mut self) -> &mut [[f32; 3]] {
+        &mut self.data.vertex
+    }
+
+    /// All vertex normals for in-place modification
+    ///
+    /// Only the values can be changed, so face indices stay valid.
+    pub fn normals_mut(&mut self) -> &mut [[f32; 3]] {
+        &mut self.data.normal
+    }
+
+    /// All vertex uvs for in-place modification
+    ///
+    /// Only the values can be changed, so face indices stay valid.
+    pub fn uvs_mut(&mut self) -> &mut [[f32; 2]] {
+        &mut self.data.texture
+    }
+
+    /// All parameter space vertices of free-form geometry
+    pub fn param_vertices(&self) -> &[[f32; 3]] {
+        &self.data.param
+    }
+}
+
+/// Element counts of an OBJ, see [`Obj::summary`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObjSummary {
+    /// Number of vertex positions
+    pub vertices: usize,
+    /// Number of vertex normals
+    pub normals: usize,
+    /// Number of vertex uvs
+    pub uvs: usize,
+    /// Number of mesh objects
+    pub meshes: usize,
+    /// Number of faces in all meshes
+    pub faces: usize,
+    /// Number of triangles the faces are split into
+    pub triangles: usize,
+    /// Number of unique material names used
+    pub materials: usize,
+    /// Number of unique material libraries used
+    pub mtllibs: usize,
+}
+
+impl core::fmt::Display for ObjSummary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Vertices: {}", self.vertices)?;
+        writeln!(f, "Normals: {}", self.normals)?;
+        writeln!(f, "UVs: {}", self.uvs)?;
+        writeln!(f, "Meshes: {}", self.meshes)?;
+        writeln!(f, "Faces: {}", self.faces)?;
+        writeln!(f, "Triangles: {}", self.triangles)?;
+        writeln!(f, "Materials: {}", self.materials)?;
+        write!(f, "Material libraries: {}", self.mtllibs)
+    }
+}
+
+#[cfg(feature = "std")]
+/// Size and timing of a parse, see [`Obj::parse_with_stats`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Size of the input in bytes
+    pub bytes: usize,
+    /// Number of lines in the input
+    pub lines: usize,
+    /// Number of vertex positions parsed
+    pub vertices: usize,
+    /// Number of faces parsed
+    pub faces: usize,
+    /// Time spent parsing
+    pub duration: std::time::Duration,
+}
+
+#[cfg(feature = "std")]
+impl ParseStats {
+    /// Parsed bytes per second
+    pub fn throughput(&self) -> f64 {
+        self.bytes as f64 / self.duration.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for ParseStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Bytes: {}", self.bytes)?;
+        writeln!(f, "Lines: {}", self.lines)?;
+        writeln!(f, "Vertices: {}", self.vertices)?;
+        writeln!(f, "Faces: {}", self.faces)?;
+        writeln!(f, "Duration: {:?}", self.duration)?;
+        write!(f, "Throughput: {:.1} MB/s", self.throughput() / 1e6)
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -51,22 +873,158 @@ struct VertexData {
     vertex: Vec<[f32; 3]>,
     normal: Vec<[f32; 3]>,
     texture: Vec<[f32; 2]>,
+    /// Third uv coordinate of each `vt`, empty unless kept
+    texture_w: Vec<f32>,
+    param: Vec<[f32; 3]>,
+}
+
+impl MeshData {
+    /// Whether the mesh has any geometry
+    fn has_elements(&self) -> bool {
+        self.faces.is_some() || !self.free_forms.is_empty()
+    }
+
+    /// Shifts all indices past `base` elements
+    fn offset(&mut self, base: Counts) {
+        let (v, t, n) = (base.vertex, base.texture, base.normal);
+
+        match &mut self.faces {
+            Some(Faces::V(faces)) => faces.iter_mut().flatten().for_each(|p| *p += v),
+            Some(Faces::VT(faces)) => faces.iter_mut().flatten().for_each(|p| {
+                p.0 += v;
+                p.1 += t;
+            }),
+            Some(Faces::VN(faces)) => faces.iter_mut().flatten().for_each(|p| {
+                p.0 += v;
+                p.1 += n;
+            }),
+            Some(Faces::VTN(faces)) => faces.iter_mut().flatten().for_each(|p| {
+                p.0 += v;
+                p.1 += t;
+                p.2 += n;
+            }),
+            None => (),
+        }
+        self.uvs2.iter_mut().flatten().for_each(|i| *i += t);
+
+        for free_form in &mut self.free_forms {
+            match &mut free_form.element {
+                FreeFormElement::Curve { vertices, .. } => {
+                    vertices.iter_mut().for_each(|i| *i += v);
+                }
+                FreeFormElement::Curve2 { vertices } => {
+                    vertices.iter_mut().for_each(|i| *i += base.param);
+                }
+                FreeFormElement::Surface { points, .. } => {
+                    for point in points {
+                        point.v += v;
+                        point.t = point.t.map(|i| i + t);
+                        point.n = point.n.map(|i| i + n);
+                    }
+                }
+            }
+            let special = free_form.special_points.iter_mut();
+            special.for_each(|i| *i += base.param);
+        }
+    }
+}
+
+/// Number of elements of each kind of vertex data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Counts {
+    vertex: usize,
+    normal: usize,
+    texture: usize,
+    param: usize,
+}
+
+impl Counts {
+    /// Sum of each kind, `None` on overflow
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self {
+            vertex: self.vertex.checked_add(other.vertex)?,
+            normal: self.normal.checked_add(other.normal)?,
+            texture: self.texture.checked_add(other.texture)?,
+            param: self.param.checked_add(other.param)?,
+        })
+    }
+
+    /// Larger count of each kind
+    fn max(self, other: Self) -> Self {
+        Self {
+            vertex: self.vertex.max(other.vertex),
+            normal: self.normal.max(other.normal),
+            texture: self.texture.max(other.texture),
+            param: self.param.max(other.param),
+        }
+    }
+}
+
+impl core::ops::Add for Counts {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            vertex: self.vertex + other.vertex,
+            normal: self.normal + other.normal,
+            texture: self.texture + other.texture,
+            param: self.param + other.param,
+        }
+    }
+}
+
+impl VertexData {
+    fn counts(&self) -> Counts {
+        Counts {
+            vertex: self.vertex.len(),
+            normal: self.normal.len(),
+            texture: self.texture.len(),
+            param: self.param.len(),
+        }
+    }
+
+    /// Appends the vertex data of `other`
+    fn append(&mut self, mut other: VertexData) {
+        self.vertex.append(&mut other.vertex);
+        self.normal.append(&mut other.normal);
+        // Keep the w coordinates parallel to the uvs if either side has them
+        if !self.texture_w.is_empty() || !other.texture_w.is_empty() {
+            self.texture_w.resize(self.texture.len(), 0.0);
+            other.texture_w.resize(other.texture.len(), 0.0);
+        }
+        self.texture.append(&mut other.texture);
+        self.texture_w.append(&mut other.texture_w);
+        self.param.append(&mut other.param);
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 struct MeshData {
-    name: Option<String>,
-    material: Option<String>,
-    mtllib: Option<std::path::PathBuf>,
-    groups: Vec<String>,
-    smoothing: u32,
+    // Shared to make splitting meshes cheap
+    name: Option<Arc<str>>,
+    material: Option<Arc<str>>,
+    mtllib: Option<String>,
+    usemap: Option<String>,
+    maplib: Option<String>,
+    groups: Vec<u32>,
+    smoothing: Option<NonZero<u32>>,
     faces: Option<Faces>,
+    raw_faces: Vec<Vec<FacePoint<NonZero<isize>>>>,
+    /// Secondary uv indices of each face, empty if the faces have none
+    uvs2: Vec<Vec<usize>>,
+    free_forms: Vec<FreeForm>,
+    /// Free-form element whose body statements are still being parsed
+    free_form: Option<FreeForm>,
+    /// Material switches inside the mesh as (first face index, material)
+    material_changes: Vec<(usize, Option<Arc<str>>)>,
+    /// Smoothing group switches inside the mesh as (first face index, group)
+    smoothing_changes: Vec<(usize, Option<NonZero<u32>>)>,
 }
 
 /// Defines the faces of a mesh.
-/// 
+///
 /// Contatins absolute 0-based indicies.
-/// 
+///
 /// Structure: Faces<Points<Index...>>
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Faces {
@@ -98,4 +1056,772 @@ impl Faces {
             Faces::VTN(faces) => faces.is_empty(),
         }
     }
+
+    /// Whether the faces contain uv indices
+    pub const fn has_uvs(&self) -> bool {
+        matches!(self, Faces::VT(_) | Faces::VTN(_))
+    }
+
+    /// Whether the faces contain normal indices
+    pub const fn has_normals(&self) -> bool {
+        matches!(self, Faces::VN(_) | Faces::VTN(_))
+    }
+
+    /// Iterates over the faces, yielding the points of each face uniformly
+    /// regardless of the format
+    pub fn iter_faces(
+        &self,
+    ) -> impl ExactSizeIterator<Item = impl ExactSizeIterator<Item = FacePoint<usize>> + '_> + '_
+    {
+        (0..self.len()).map(move |face| (0..self.face_len(face)).map(move |i| self.point(face, i)))
+    }
+
+    /// Adds normal indices given by `normal_of` for each position index
+    fn with_normals(self, normal_of: impl Fn(usize) -> usize) -> Self {
+        fn convert<T, O>(faces: Vec<Vec<T>>, f: impl Fn(T) -> O) -> Vec<Vec<O>> {
+            let faces = faces.into_iter();
+            faces
+                .map(|face| face.into_iter().map(&f).collect())
+                .collect()
+        }
+
+        match self {
+            Faces::V(faces) => Faces::VN(convert(faces, |v| (v, normal_of(v)))),
+            Faces::VT(faces) => Faces::VTN(convert(faces, |(v, t)| (v, t, normal_of(v)))),
+            faces => faces,
+        }
+    }
+
+    /// Removes the last face
+    fn pop(&mut self) {
+        match self {
+            Faces::V(faces) => drop(faces.pop()),
+            Faces::VT(faces) => drop(faces.pop()),
+            Faces::VN(faces) => drop(faces.pop()),
+            Faces::VTN(faces) => drop(faces.pop()),
+        }
+    }
+
+    /// Copies a range of faces
+    fn slice(&self, range: core::ops::Range<usize>) -> Self {
+        match self {
+            Faces::V(faces) => Faces::V(faces[range].to_vec()),
+            Faces::VT(faces) => Faces::VT(faces[range].to_vec()),
+            Faces::VN(faces) => Faces::VN(faces[range].to_vec()),
+            Faces::VTN(faces) => Faces::VTN(faces[range].to_vec()),
+        }
+    }
+
+    /// Number of points of a face
+    ///
+    /// # Panics
+    ///
+    /// Panics if the face index is out of range.
+    pub fn face_len(&self, face: usize) -> usize {
+        match self {
+            Faces::V(faces) => faces[face].len(),
+            Faces::VT(faces) => faces[face].len(),
+            Faces::VN(faces) => faces[face].len(),
+            Faces::VTN(faces) => faces[face].len(),
+        }
+    }
+
+    /// Whether a face has exactly 3 points
+    ///
+    /// # Panics
+    ///
+    /// Panics if the face index is out of range.
+    pub fn is_triangle(&self, face: usize) -> bool {
+        self.face_len(face) == 3
+    }
+
+    /// Whether a face has exactly 4 points
+    ///
+    /// # Panics
+    ///
+    /// Panics if the face index is out of range.
+    pub fn is_quad(&self, face: usize) -> bool {
+        self.face_len(face) == 4
+    }
+
+    /// Point `i` of a face, regardless of the format
+    ///
+    /// # Panics
+    ///
+    /// Panics if the face or point index is out of range.
+    pub fn point(&self, face: usize, i: usize) -> FacePoint<usize> {
+        let (v, t, n) = match self {
+            Faces::V(faces) => (faces[face][i], None, None),
+            Faces::VT(faces) => (faces[face][i].0, Some(faces[face][i].1), None),
+            Faces::VN(faces) => (faces[face][i].0, None, Some(faces[face][i].1)),
+            Faces::VTN(faces) => {
+                let (v, t, n) = faces[face][i];
+                (v, Some(t), Some(n))
+            }
+        };
+        FacePoint { v, t, n }
+    }
+
+    /// Creates faces from points
+    ///
+    /// The format is selected by the presence of the default uv and normal
+    /// indices, which are used for points missing them.
+    fn from_points<F, P>(faces: F, uv: Option<usize>, normal: Option<usize>) -> Self
+    where
+        F: Iterator<Item = P>,
+        P: Iterator<Item = FacePoint<usize>>,
+    {
+        fn convert<F, P, T>(faces: F, f: impl Fn(FacePoint<usize>) -> T) -> Vec<Vec<T>>
+        where
+            F: Iterator<Item = P>,
+            P: Iterator<Item = FacePoint<usize>>,
+        {
+            faces.map(|face| face.map(&f).collect()).collect()
+        }
+
+        match (uv, normal) {
+            (None, None) => Faces::V(convert(faces, |p| p.v)),
+            (Some(dt), None) => Faces::VT(convert(faces, |p| (p.v, p.t.unwrap_or(dt)))),
+            (None, Some(dn)) => Faces::VN(convert(faces, |p| (p.v, p.n.unwrap_or(dn)))),
+            (Some(dt), Some(dn)) => Faces::VTN(convert(faces, |p| {
+                (p.v, p.t.unwrap_or(dt), p.n.unwrap_or(dn))
+            })),
+        }
+    }
+}
+
+/// Unit normal of a face, `[0, 0, 1]` if it is degenerate
+fn flat_normal(positions: &[[f32; 3]], face: &[FacePoint<usize>]) -> [f32; 3] {
+    let points = face
+        .iter()
+        .map(|p| positions.get(p.v).copied().unwrap_or_default());
+    match math::normalize(math::newell(points)) {
+        [0.0, 0.0, 0.0] => [0.0, 0.0, 1.0],
+        normal => normal,
+    }
+}
+
+#[cfg(feature = "trimesh")]
+/// Area weighted normals of triangulated vertices, `[0, 0, 1]` where degenerate
+fn smooth_normals(positions: &[[f32; 3]], indices: &[usize]) -> Vec<[f32; 3]> {
+    let mut normals = vec![[0.0; 3]; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i]]);
+        let normal = math::cross(math::sub(b, a), math::sub(c, a));
+        for &i in triangle {
+            normals[i] = math::add(normals[i], normal);
+        }
+    }
+    let normals = normals.into_iter().map(math::normalize);
+    normals
+        .map(|normal| match normal {
+            [0.0, 0.0, 0.0] => [0.0, 0.0, 1.0],
+            normal => normal,
+        })
+        .collect()
+}
+
+/// Face point with optional uv and normal indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FacePoint<I> {
+    /// Vertex position index
+    pub v: I,
+    /// Vertex uv index
+    pub t: Option<I>,
+    /// Vertex normal index
+    pub n: Option<I>,
+}
+
+impl<I: Copy> FacePoint<I> {
+    /// Vertex position index
+    pub const fn vertex(&self) -> I {
+        self.v
+    }
+
+    /// Vertex uv index, if the point has one
+    pub const fn texture(&self) -> Option<I> {
+        self.t
+    }
+
+    /// Vertex normal index, if the point has one
+    pub const fn normal(&self) -> Option<I> {
+        self.n
+    }
+}
+
+/// Formats the point as written in a face statement, e.g. `1/2/3` or `1//3`
+impl<I: core::fmt::Display> core::fmt::Display for FacePoint<I> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.v)?;
+        match (&self.t, &self.n) {
+            (Some(t), Some(n)) => write!(f, "/{t}/{n}"),
+            (Some(t), None) => write!(f, "/{t}"),
+            (None, Some(n)) => write!(f, "//{n}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math;
+
+    #[test]
+    fn iter_faces() {
+        let faces = Faces::VT(vec![
+            vec![(0, 1), (1, 2), (2, 3)],
+            vec![(3, 0), (2, 1), (1, 2), (0, 3)],
+        ]);
+        let points: Vec<Vec<_>> = faces.iter_faces().map(Iterator::collect).collect();
+
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1].len(), 4);
+        assert_eq!(
+            points[0][1],
+            FacePoint {
+                v: 1,
+                t: Some(2),
+                n: None
+            }
+        );
+    }
+
+    #[test]
+    fn face_points() {
+        let faces = Faces::VN(vec![
+            vec![(0, 1), (1, 2), (2, 3)],
+            vec![(3, 0), (2, 1), (1, 2), (0, 3)],
+        ]);
+        assert_eq!(faces.face_len(0), 3);
+        assert_eq!(faces.face_len(1), 4);
+
+        let point = faces.point(1, 2);
+        assert_eq!(point.vertex(), 1);
+        assert_eq!(point.texture(), None);
+        assert_eq!(point.normal(), Some(2));
+    }
+
+    #[test]
+    fn single_mesh() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvn 0 0 1\n\
+            o A\nf 1 2 3\no B\nf 2//1 4//1 3//1\n",
+        )
+        .unwrap();
+        assert_eq!(obj.meshes().len(), 2);
+
+        let obj = obj.into_single_mesh();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name(), None);
+        assert_eq!(
+            meshes[0].faces(),
+            &Faces::VN(vec![
+                vec![(0, 1), (1, 1), (2, 1)],
+                vec![(1, 0), (3, 0), (2, 0)]
+            ])
+        );
+        // Mesh A gets its flat normal instead of a zero vector
+        assert_eq!(obj.normals()[1], [0.0, 0.0, 1.0]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_triangulate() {
+        let mut bytes = String::from("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n");
+        for i in 0..64 {
+            bytes += &format!("o Mesh{i}\nf 1 2 4 3\n");
+        }
+        let obj = Obj::parse(bytes.as_bytes()).unwrap();
+
+        let meshes = obj.par_triangulate();
+        assert_eq!(meshes.len(), 64);
+        for (parallel, mesh) in meshes.into_iter().zip(obj.meshes()) {
+            assert_eq!(parallel.unwrap(), mesh.triangulate().unwrap());
+        }
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn triangulate_all() {
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\n\
+            o A\nf 1 2 3\no B\nf 2/1 4/1 3/1\n",
+        )
+        .unwrap();
+
+        let (indices, vertices) = obj.triangulate_all().unwrap();
+        assert_eq!(indices.0, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(vertices.positions.len(), 6);
+        assert_eq!(vertices.uvs.as_ref().map(Vec::len), Some(6));
+        assert!(vertices.normals.is_none());
+
+        // Points shared between meshes become one vertex
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\no A\nf 1 2 3\no B\nf 2 4 3\n");
+        let (indices, vertices) = obj.unwrap().triangulate_all().unwrap();
+        assert_eq!(indices.0, [0, 1, 2, 1, 3, 2]);
+        assert_eq!(vertices.positions.len(), 4);
+
+        // Meshes without normals get smooth ones
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvn 1 0 0\n\
+            o A\nf 1//1 2//1 3//1\no B\nf 2 4 3\n",
+        )
+        .unwrap();
+        let (indices, vertices) = obj.triangulate_all().unwrap();
+        assert_eq!(indices.0, [0, 1, 2, 3, 4, 5]);
+        let normals = vertices.normals.unwrap();
+        assert_eq!(normals[..3], [[1.0, 0.0, 0.0]; 3]);
+        assert_eq!(normals[3..], [[0.0, 0.0, 1.0]; 3]);
+    }
+
+    #[test]
+    fn modify_uvs() {
+        let mut obj = Obj::parse(b"v 0 0 0\nvt 0.5 0.25\nvt 1 1\nf 1/1 1/2 1/1\n").unwrap();
+        for uv in obj.uvs_mut() {
+            *uv = uv.map(|c| c * 2.0);
+        }
+
+        assert_eq!(obj.uvs(), [[1.0, 0.5], [2.0, 2.0]]);
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::VT(vec![vec![(0, 0), (0, 1), (0, 0)]])
+        );
+    }
+
+    #[test]
+    fn flat_arrays() {
+        let obj =
+            Obj::parse(b"v 1 2 3\nv 4 5 6\nvn 0 1 0\nvt 0.5 0.25\nf 1/1/1 2/1/1 1/1/1\n").unwrap();
+        assert_eq!(obj.vertices_flat(), [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(obj.vertices_flat().as_ptr(), obj.vertices().as_ptr().cast());
+        assert_eq!(obj.normals_flat(), [0.0, 1.0, 0.0]);
+        assert_eq!(obj.uvs_flat(), [0.5, 0.25]);
+    }
+
+    #[test]
+    fn generate_normals() {
+        let mut obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nvt 0 0\nvn 1 0 0\n\
+            f 1/1 2/1 3/1\no Side\nf 1 4 2\no Normals\nf 1//1 2//1 3//1\n",
+        )
+        .unwrap();
+        obj.generate_normals(NormalWeighting::Area);
+
+        assert_eq!(obj.normals().len(), 5);
+        assert_eq!(obj.normals()[3], [0.0, 0.0, 1.0]);
+        assert_eq!(obj.normals()[4], [0.0, 1.0, 0.0]);
+
+        let meshes = obj.meshes();
+        assert_eq!(
+            meshes[0].faces(),
+            &Faces::VTN(vec![vec![(0, 0, 1), (1, 0, 2), (2, 0, 3)]])
+        );
+        assert_eq!(
+            meshes[1].faces(),
+            &Faces::VN(vec![vec![(0, 1), (3, 4), (1, 2)]])
+        );
+        assert_eq!(
+            meshes[2].faces(),
+            &Faces::VN(vec![vec![(0, 0), (1, 0), (2, 0)]])
+        );
+
+        let normal = math::normalize([0.0, 1.0, 1.0]);
+        for (a, b) in obj.normals()[1].iter().zip(normal) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        #[cfg(feature = "trimesh")]
+        assert!(meshes[1].triangulate().unwrap().1.normals.is_some());
+
+        // A second call finds no faces without normals
+        obj.generate_normals(NormalWeighting::Area);
+        assert_eq!(obj.normals().len(), 5);
+
+        // Files with normals everywhere are left alone
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+        let mut obj = Obj::parse(bytes).unwrap();
+        obj.generate_normals(NormalWeighting::Area);
+        assert_eq!(obj.normals(), [[0.0, 0.0, 1.0]]);
+
+        // Positions not used by faces without normals get none
+        let mut obj = Obj::parse(b"v 5 5 5\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 2 3 4\n").unwrap();
+        obj.generate_normals(NormalWeighting::Area);
+        assert_eq!(obj.normals(), [[0.0, 0.0, 1.0]; 3]);
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::VN(vec![vec![(1, 0), (2, 1), (3, 2)]])
+        );
+    }
+
+    #[test]
+    fn generate_crease_normals() {
+        // Two objects meeting at an edge, gently folded or at a right angle.
+        // B either reuses the edge vertices or has its own copies of them.
+        let cases = [("0.1", true), ("-1", false)];
+        for ((side, shared), b_face) in cases
+            .into_iter()
+            .flat_map(|case| [(case, "f 2 5 6 3"), (case, "f 7 5 6 8")])
+        {
+            let bytes = format!(
+                "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 {side}\nv 2 1 {side}\n\
+                v 1 0 0\nv 1 1 0\no A\nf 1 2 3 4\no B\n{b_face}\n"
+            );
+            let mut obj = Obj::parse(bytes.as_bytes()).unwrap();
+            obj.generate_crease_normals(NormalWeighting::Area, 30f32.to_radians());
+
+            let meshes = obj.meshes();
+            let (Faces::VN(a), Faces::VN(b)) = (meshes[0].faces(), meshes[1].faces()) else {
+                panic!("faces should have normals");
+            };
+            // The edge is at points 1 and 2 of A and 0 and 3 of B
+            let normal = |point: &(usize, usize)| obj.normals()[point.1];
+            assert_eq!(normal(&a[0][1]) == normal(&b[0][0]), shared);
+            assert_eq!(normal(&a[0][2]) == normal(&b[0][3]), shared);
+            assert_eq!(obj.normals()[a[0][0].1], [0.0, 0.0, 1.0]);
+            if !shared {
+                assert_eq!(obj.normals()[a[0][1].1], [0.0, 0.0, 1.0]);
+            }
+        }
+    }
+
+    #[test]
+    fn mesh_selection() {
+        let obj = Obj::parse(
+            b"v 0 0 0\no Body\ng car paint\nf 1 1 1\no Wheel\ng car\nf 1 1 1\n\
+            o Glass\ng car glass\nf 1 1 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(obj.mesh_by_name("Wheel").unwrap().groups().len(), 1);
+        assert!(obj.mesh_by_name("wheel").is_none());
+        assert_eq!(obj.mesh(2).unwrap().name(), Some("Glass"));
+        assert!(obj.mesh(3).is_none() && obj.mesh_count() == 3);
+
+        let names = |group| -> Vec<_> {
+            let meshes = obj.meshes_in_group(group);
+            meshes.map(|m| m.name().unwrap().to_string()).collect()
+        };
+        assert_eq!(names("car"), ["Body", "Wheel", "Glass"]);
+        assert_eq!(names("glass"), ["Glass"]);
+        assert!(names("Car").is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn called_files() {
+        let root = b"v 0 0 0\nv 1 0 0\nv 0 1 0\ng root\nf 1 2 3\ncall part.obj 1 2\nf -3 -2 -1\n";
+        let part = b"v 5 5 5\nv 6 5 5\nv 5 6 5\nvt 0 0\ng part\nf 3/1 2/1 1/1\n";
+
+        let mut calls = Vec::new();
+        let obj = Obj::parse_with_loader(root, ParseOptions::default(), |path| {
+            calls.push(path.to_owned());
+            Ok(part.to_vec())
+        })
+        .unwrap();
+        assert_eq!(calls, ["part.obj"]);
+        assert_eq!(obj.vertices().len(), 6);
+
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 3);
+        assert_eq!(meshes[1].group_names().collect::<Vec<_>>(), ["part"]);
+        assert_eq!(
+            meshes[1].faces(),
+            &Faces::VT(vec![vec![(5, 0), (4, 0), (3, 0)]])
+        );
+        // Relative indices after the call see the inlined vertices
+        assert_eq!(meshes[2].faces(), &Faces::V(vec![vec![3, 4, 5]]));
+        assert_eq!(meshes[2].group_names().collect::<Vec<_>>(), ["root"]);
+
+        // Without a loader the call is skipped
+        assert_eq!(Obj::parse(root).unwrap().vertices().len(), 3);
+
+        let cycle = Obj::parse_with_loader(b"call a.obj\n", ParseOptions::default(), |path| {
+            Ok(match path {
+                "a.obj" => b"call b.obj\n".to_vec(),
+                _ => b"call a.obj\n".to_vec(),
+            })
+        });
+        assert!(cycle.unwrap_err().to_string().contains("include cycle"));
+    }
+
+    #[test]
+    fn summary() {
+        let obj = Obj::parse(include_bytes!("../../assets/cube.obj")).unwrap();
+        let summary = obj.summary();
+        assert_eq!(
+            summary,
+            ObjSummary {
+                vertices: 8,
+                normals: 6,
+                uvs: 14,
+                meshes: 1,
+                faces: 6,
+                triangles: 12,
+                materials: 1,
+                mtllibs: 1,
+            }
+        );
+        assert!(summary.to_string().starts_with("Vertices: 8\nNormals: 6\n"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parse_stats() {
+        let bytes = include_bytes!("../../assets/cube.obj");
+        let (obj, stats) = Obj::parse_with_stats(bytes, ParseOptions::default()).unwrap();
+        assert_eq!(stats.bytes, bytes.len());
+        assert_eq!(stats.lines, bytes.split(|b| *b == b'\n').count() - 1);
+        assert_eq!((stats.vertices, stats.faces), (8, obj.face_count()));
+        assert!(stats.throughput() > 0.0);
+
+        let (_, stats) = Obj::parse_with_stats(b"v 0 0 0\r\nv 1 1 1", Default::default()).unwrap();
+        assert_eq!((stats.lines, stats.vertices), (2, 2));
+
+        let bytes = b"# header\n\nv 0 0 0\n\n# end";
+        let (_, stats) = Obj::parse_with_stats(bytes, Default::default()).unwrap();
+        assert_eq!((stats.lines, stats.vertices), (5, 1));
+    }
+
+    #[cfg(feature = "trimesh")]
+    #[test]
+    fn to_interleaved() {
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3\nf 2 4 3\n").unwrap();
+        let (vertices, indices) = obj.to_interleaved().unwrap();
+        assert_eq!(indices, [0, 1, 2, 1, 3, 2]);
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[3].position, [1.0, 1.0, 0.0]);
+        assert!(
+            vertices
+                .iter()
+                .all(|v| v.normal == Vertex::DEFAULT_NORMAL && v.uv == [0.0; 2])
+        );
+
+        let cube = Obj::parse(include_bytes!("../../assets/cube.obj")).unwrap();
+        let (vertices, indices) = cube.to_interleaved().unwrap();
+        assert_eq!(indices.len(), 36);
+        assert!(
+            vertices
+                .iter()
+                .all(|v| v.normal != Vertex::DEFAULT_NORMAL || v.position[2] > 0.0)
+        );
+
+        // Only the first mesh has normals, the second gets generated ones
+        let mixed = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 1 0 0\no A\nf 1//1 2//1 3//1\no B\nf 1 2 3\n",
+        )
+        .unwrap();
+        let (vertices, indices) = mixed.to_interleaved().unwrap();
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+        assert!(vertices[..3].iter().all(|v| v.normal == [1.0, 0.0, 0.0]));
+        assert!(vertices[3..].iter().all(|v| v.normal == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn parse_with_progress() {
+        let bytes = "v 0 0 0\n".repeat(10000);
+        let mut reports = Vec::new();
+        let obj = Obj::parse_with_progress(bytes.as_bytes(), Default::default(), |done, total| {
+            reports.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(obj.vertices().len(), 10000);
+        assert_eq!(
+            reports,
+            [(4096 * 8, 80000), (8192 * 8, 80000), (80000, 80000)]
+        );
+    }
+
+    #[test]
+    fn face_point_display() {
+        let point = |v, t, n| FacePoint { v, t, n }.to_string();
+        assert_eq!(point(1, None, None), "1");
+        assert_eq!(point(1, Some(2), None), "1/2");
+        assert_eq!(point(1, None, Some(3)), "1//3");
+        assert_eq!(point(1, Some(2), Some(3)), "1/2/3");
+
+        // Raw faces are written back with their relative indices
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 -2//-1 3//1\n";
+        let obj = Obj::parse_raw_indices(bytes).unwrap();
+        let points: Vec<_> = obj.meshes()[0].raw_faces()[0]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(points.join(" "), "1//1 -2//-1 3//1");
+    }
+
+    #[test]
+    fn face_shapes() {
+        let obj = Obj::parse(b"v 0 0 0\nf 1 1 1\nf 1 1 1 1\nf 1 1 1 1 1\n").unwrap();
+        let meshes = obj.meshes();
+        let faces = meshes[0].faces();
+        let shapes: Vec<_> = (0..faces.len())
+            .map(|i| (faces.is_triangle(i), faces.is_quad(i)))
+            .collect();
+        assert_eq!(shapes, [(true, false), (false, true), (false, false)]);
+    }
+
+    #[test]
+    fn parse_filtered() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvp 0 0\nvp 1 0\nf 1 2 3\n\
+            o Wheel\nf 1 2 3\nf not a face\ncurv 0 1 1 2\nparm u 0 1\nend\n\
+            o Body\nf 3 2 1\no Empty\n";
+        let mut names = Vec::new();
+        let obj = Obj::parse_filtered(bytes, ParseOptions::default(), |name| {
+            names.push(name.map(String::from));
+            name == Some("Body")
+        })
+        .unwrap();
+        let expected = [None, Some("Wheel"), Some("Body"), Some("Empty")];
+        assert_eq!(names, expected.map(|name| name.map(String::from)));
+        assert_eq!(obj.vertices().len(), 3);
+        assert_eq!(obj.meshes().len(), 1);
+        assert_eq!(obj.meshes()[0].name(), Some("Body"));
+        assert_eq!(obj.meshes()[0].faces().len(), 1);
+        assert!(obj.meshes()[0].free_forms().is_empty());
+
+        // Rejected objects are dropped even if empty objects are kept
+        let options = ParseOptions {
+            keep_empty_objects: true,
+            ..Default::default()
+        };
+        let obj = Obj::parse_filtered(bytes, options, |name| name != Some("Wheel")).unwrap();
+        let meshes = obj.meshes();
+        let names: Vec<_> = meshes.iter().map(|m| m.name()).collect();
+        assert_eq!(names, [None, Some("Body"), Some("Empty")]);
+    }
+
+    #[test]
+    fn parse_with_offset() {
+        let first = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+        let second = b"v 1 1 0\nf 2//1 4//1 -2//-1\n";
+
+        let options = ParseOptions::default();
+        let chunk = Obj::parse_with_offset(second, options, 3, 1, 0).unwrap();
+        let whole = Obj::parse(&[&first[..], second].concat()).unwrap();
+        assert_eq!(chunk.vertices(), &whole.vertices()[3..]);
+        assert_eq!(
+            chunk.meshes()[0].faces(),
+            &whole.meshes()[0].faces().slice(1..2)
+        );
+
+        // Indices past the offset data are still checked
+        assert!(Obj::parse_with_offset(b"v 0 0 0\nf 1 2 5\n", options, 3, 0, 0).is_err());
+
+        // Offsets which would overflow are rejected instead of wrapping
+        let error = Obj::parse_with_offset(second, options, usize::MAX - 1, 0, 0).unwrap_err();
+        assert!(error.to_string().contains("offset is too large"), "{error}");
+    }
+
+    #[test]
+    fn parse_str() {
+        let text = "mtllib matériaux.mtl\no Würfel\ng grüne #Ecke\nusemtl Stahl·1\n\
+            v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let obj = Obj::parse_str(text).unwrap();
+        let mesh = obj.meshes()[0];
+        assert_eq!(mesh.name(), Some("Würfel"));
+        assert_eq!(mesh.group_names().collect::<Vec<_>>(), ["grüne"]);
+        assert_eq!(mesh.material(), Some("Stahl·1"));
+        assert_eq!(mesh.mtllib(), Some("matériaux.mtl"));
+    }
+
+    #[test]
+    fn combined_hooks() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv x 0 0\nfoo\nf 2 3 4\nf 3 4 5\n";
+        let (mut errors, mut warnings, mut lines) = (Vec::new(), Vec::new(), 0);
+        let hooks = Hooks {
+            warnings: Some(Warnings::new(bytes, &mut warnings)),
+            errors: Some(&mut errors),
+            offset: Counts {
+                vertex: 1,
+                ..Default::default()
+            },
+            lines: Some(&mut lines),
+            ..Default::default()
+        };
+        let obj = Obj::parse_inner(bytes, &ParseOptions::default(), hooks).unwrap();
+
+        // Indices start after the offset vertex. The skipped vertex and the
+        // face using it are errors, the unknown keyword only a warning
+        assert_eq!(errors.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(lines, 7);
+        assert_eq!(obj.meshes()[0].faces().len(), 1);
+    }
+
+    #[test]
+    fn merge() {
+        const CUBE: &[u8] = b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+            v 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\n\
+            vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 -1\nvn 0 0 1\n\
+            g cube\n\
+            f 1/1/1 4/4/1 3/3/1 2/2/1\nf 5/1/2 6/2/2 7/3/2 8/4/2\n\
+            f 1/1/1 2/2/1 6/3/1 5/4/1\nf 2/1/1 3/2/1 7/3/1 6/4/1\n\
+            f 3/1/1 4/2/1 8/3/1 7/4/1\nf 4/1/1 1/2/1 5/3/1 8/4/1\n";
+
+        let mut obj = Obj::parse(CUBE).unwrap();
+        let mut other = Obj::parse(CUBE).unwrap();
+        other.vertices_mut().iter_mut().for_each(|v| v[0] += 10.0);
+        obj.merge(other).unwrap();
+
+        assert_eq!(obj.vertices().len(), 16);
+        assert_eq!(obj.uvs().len(), 8);
+        assert_eq!(obj.normals().len(), 4);
+        assert_eq!(obj.groups.len(), 1);
+
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[1].group_names().collect::<Vec<_>>(), ["cube"]);
+        for (first, second) in meshes[0]
+            .faces()
+            .iter_faces()
+            .zip(meshes[1].faces().iter_faces())
+        {
+            for (a, b) in first.zip(second) {
+                let (pa, pb) = (obj.vertices()[a.v], obj.vertices()[b.v]);
+                assert_eq!([pa[0] + 10.0, pa[1], pa[2]], pb);
+                assert_eq!(b.t, a.t.map(|t| t + 4));
+                assert_eq!(b.n, a.n.map(|n| n + 2));
+            }
+        }
+    }
+
+    #[cfg(feature = "memmap")]
+    #[test]
+    fn parse_mmap() {
+        let path = std::env::temp_dir().join(format!("wobj-mmap-{}.obj", std::process::id()));
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let obj = unsafe { Obj::parse_mmap(&path) };
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(obj.unwrap().meshes()[0].faces().len(), 1);
+
+        let error = unsafe { Obj::parse_mmap(&path) }.unwrap_err();
+        assert!(error.to_string().starts_with("failed to map"));
+        let io = error.io_error().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn material_search_paths() {
+        let root = std::env::temp_dir().join(format!("wobj-search-{}", std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(second.join("test.mtl"), "newmtl Mat\nKd 1 0 0\n").unwrap();
+
+        let obj = Obj::parse(b"mtllib test.mtl\nv 0 0 0\nusemtl Mat\nf 1 1 1\n").unwrap();
+        let mtls = obj.load_materials(&[first.clone(), second]).unwrap();
+        assert!(mtls["test.mtl"].get("Mat").is_some());
+
+        let error = obj
+            .load_materials(std::slice::from_ref(&first))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains(&first.join("test.mtl").display().to_string()));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
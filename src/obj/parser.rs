@@ -1,141 +1,1002 @@
-use std::num::NonZero;
+use core::cell::Cell;
+use core::num::NonZero;
 
-use winnow::ascii::{dec_int, dec_uint, float, space1};
-use winnow::combinator::{alt, delimited, opt, preceded, separated, separated_pair, seq};
-use winnow::error::ContextError;
+use winnow::ascii::{dec_int, dec_uint, float, multispace0, space0, space1};
+use winnow::combinator::{
+    alt, delimited, eof, opt, peek, preceded, separated, separated_pair, seq, terminated,
+};
+use winnow::error::{ContextError, FromExternalError};
 use winnow::{BStr, Result, prelude::*};
 
-use super::{Faces, MeshData, Obj, VertexData};
+use super::{
+    Comment, Counts, CurveLoop, CurveLoopKind, CurveSegment, FacePoint, Faces, FreeForm,
+    FreeFormElement, GroupNames, MeshData, Obj, ObjEvent, VertexData,
+};
+use crate::compat::*;
 use crate::util::{
-    description, expected, ignoreable, label, parse_path, parse_string, to_next_line, word,
+    LineCounter, Utf8, decode, description, expected, ignoreable, label, line_end, parse_path,
+    parse_str, parse_string, skip_bom, till_comment, till_line_end, to_next_line, unknown_keyword,
+    word,
 };
+use crate::warning::Warnings;
+use crate::{Grouping, InvalidUtf8, ParseOptions, WarningKind, WobjError};
+
+type Loader<'a> = &'a mut dyn FnMut(&str) -> Result<Vec<u8>, WobjError>;
+
+/// Receives the bytes consumed and the total
+type Progress<'a> = &'a mut dyn FnMut(usize, usize);
+
+/// Receives the object name and returns whether to keep its faces
+type Filter<'a> = &'a mut dyn FnMut(Option<&str>) -> bool;
+
+/// Statements parsed between progress reports
+const PROGRESS_INTERVAL: usize = 4096;
+
+/// Optional state and callbacks of [`parse_obj`], which can be combined freely
+#[derive(Default)]
+pub(crate) struct Hooks<'a, 'l> {
+    /// Collects warnings, disabled if missing
+    pub warnings: Option<Warnings<'a>>,
+    /// Handles `call` statements, which are ignored if missing
+    pub includes: Option<&'a mut Includes<'l>>,
+    /// Collects the errors of skipped statements, parsing stops on the first error if missing
+    pub errors: Option<&'a mut Vec<WobjError>>,
+    /// Number of elements preceding the input, see [`Obj::parse_with_offset`]
+    pub offset: Counts,
+    pub progress: Option<Progress<'a>>,
+    pub filter: Option<Filter<'a>>,
+    /// Receives the number of lines in the input
+    pub lines: Option<&'a mut usize>,
+    /// The input is a `&str`, so names and paths need no UTF-8 validation
+    pub valid_utf8: bool,
+}
+
+/// Loader for the files referenced by `call` statements
+pub(crate) struct Includes<'a> {
+    loader: Option<Loader<'a>>,
+    // Files currently being parsed, to detect cycles
+    stack: Vec<String>,
+}
 
-pub(crate) fn parse_obj(input: &mut &BStr) -> Result<Obj> {
+impl<'a> Includes<'a> {
+    pub fn new(loader: Option<Loader<'a>>) -> Self {
+        Self {
+            loader,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Whether `call` statements are handled
+    fn enabled(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Loads and parses the called file
+    fn parse(&mut self, path: &str, options: &ParseOptions) -> Result<Obj, WobjError> {
+        if self.stack.iter().any(|p| p == path) {
+            return Err(format!("include cycle through '{path}'").into());
+        }
+        let Some(loader) = &mut self.loader else {
+            return Err("no loader for called files".into());
+        };
+        let bytes = loader(path)?;
+
+        self.stack.push(path.to_owned());
+        let mut hooks = Hooks {
+            includes: Some(self),
+            ..Default::default()
+        };
+        let obj = (|input: &mut &BStr| parse_obj(input, options, &mut hooks))
+            .parse(BStr::new(&bytes))
+            .map_err(|e| WobjError::from(e).map_message(|e| format!("in '{path}': {e}")));
+        self.stack.pop();
+        obj
+    }
+}
+
+pub(crate) fn parse_obj(
+    input: &mut &BStr,
+    options: &ParseOptions,
+    hooks: &mut Hooks,
+) -> Result<Obj> {
+    let mut no_warnings = Warnings::disabled();
+    let warnings = hooks.warnings.as_mut().unwrap_or(&mut no_warnings);
+    let mut no_includes = Includes::new(None);
+    let includes = hooks.includes.as_deref_mut().unwrap_or(&mut no_includes);
+    let mut errors = hooks.errors.as_deref_mut();
+    let offset = hooks.offset;
+    let mut progress = hooks.progress.as_deref_mut();
+    let mut filter = hooks.filter.as_deref_mut();
+    let line_count = hooks.lines.as_deref_mut();
+    skip_bom(input)?;
+    let source = *input;
+    let mut lines = LineCounter::new(input);
+    let total = input.len();
+    let mut statements = 0usize;
     let mut data = VertexData::default();
+    // Indices are resolved as if the data was preceded by `offset` elements
+    let counts = |data: &VertexData| data.counts() + offset;
+    // Final data counts, which forward referencing faces are checked against
+    let totals = options
+        .forward_references
+        .then(|| count_data(input) + offset);
     let mut meshes = Vec::new();
+    let mut groups = GroupNames::default();
     let mut current = MeshData::default();
+    let split_on_attributes = options.grouping == Grouping::Attributes;
+    let split_on_groups = options.grouping == Grouping::Group;
+    let utf8 = match hooks.valid_utf8 {
+        true => Utf8::Valid,
+        false => Utf8::Checked(options.on_invalid_utf8),
+    };
+
+    // Whether the filter rejects the current object, whose elements are then skipped
+    let mut rejected =
+        |name: &Option<Arc<str>>| filter.as_mut().is_some_and(|keep| !keep(name.as_deref()));
+    let skip_object = Cell::new(rejected(&None));
 
     // Check if the current mesh needs to be added to meshes
-    let mut check = |current: &mut MeshData| {
-        if current.faces.is_some() {
-            meshes.push(current.clone());
+    let mut check = |current: &mut MeshData, object_end: bool| {
+        // Free-form elements belong to the object they were started in
+        if object_end {
+            current.free_forms.extend(current.free_form.take());
+        }
+
+        // Meshes split by attributes share the name, so an object is new if
+        // its name was not pushed yet
+        let empty_object = object_end
+            && !skip_object.get()
+            && options.keep_empty_objects
+            && current.name.as_ref().is_some_and(|name| {
+                let last = meshes.last().and_then(|m: &MeshData| m.name.as_ref());
+                !last.is_some_and(|last| Arc::ptr_eq(last, name))
+            });
+        if current.has_elements() || empty_object {
+            meshes.push(MeshData {
+                free_form: None,
+                ..current.clone()
+            });
             current.faces = None;
+            current.raw_faces.clear();
+            current.uvs2.clear();
+            current.free_forms.clear();
+
+            // Continue with the last material and smoothing group in effect
+            if let Some((_, material)) = current.material_changes.pop() {
+                current.material = material;
+            }
+            current.material_changes.clear();
+            if let Some((_, smoothing)) = current.smoothing_changes.pop() {
+                current.smoothing = smoothing;
+            }
+            current.smoothing_changes.clear();
         }
     };
 
+    // Whether the name and material were recovered from comments
+    let (mut comment_name, mut comment_material) = (false, false);
+    let mut comments = Vec::new();
+    let (mut shadow_obj, mut trace_obj) = (None, None);
+    let read_comments = options.comment_fallback || options.capture_comments;
+
+    // Elements skipped by lossy parsing
+    let mut skipped = Skipped::default();
+
+    // Free-form state
+    let (mut rational, mut cstype, mut degree) = (false, String::new(), [0, 0]);
+
+    loop {
+        while read_comments && let Some((start, comment)) = next_comment(input)? {
+            if options.capture_comments {
+                let line = lines.line(start);
+                let text = String::from_utf8_lossy(comment.trim_ascii()).into_owned();
+                comments.push(Comment { line, text });
+            }
+            if !options.comment_fallback {
+                continue;
+            }
+
+            match comment_statement(comment, utf8) {
+                Some((b"object", name)) if current.name.is_none() || comment_name => {
+                    check(&mut current, true);
+                    current.name = Some(Arc::from(name));
+                    skip_object.set(rejected(&current.name));
+                    comment_name = true;
+                }
+                Some((b"usemtl", material)) if current.material.is_none() || comment_material => {
+                    let material = Some(Arc::from(material));
+                    use_material(&mut current, material, split_on_attributes, &mut check);
+                    comment_material = true;
+                }
+                _ => (),
+            }
+        }
+
+        let Ok(key) = keyword(input) else {
+            break;
+        };
+
+        // Parse the statement separately so its errors can be recovered from
+        let statement = *input;
+        let result = (|input: &mut &BStr| -> Result<()> {
+            match key {
+                b"v" => data.vertex.push(
+                    parse_float3
+                        .context(label("vertex geometry"))
+                        .parse_next(input)?,
+                ),
+                b"vn" => data.normal.push(
+                    parse_float3
+                        .context(label("vertex normal"))
+                        .parse_next(input)?,
+                ),
+                b"vt" => {
+                    let [u, v, w] = parse_vt
+                        .context(label("vertex texture"))
+                        .parse_next(input)?;
+                    data.texture.push([u, v]);
+                    if options.uv_w {
+                        data.texture_w.push(w);
+                    }
+                }
+                b"vp" => data.param.push(
+                    parse_vp
+                        .context(label("parameter space vertex"))
+                        .parse_next(input)?,
+                ),
+                b"f" if skip_object.get() => (),
+                b"f" => {
+                    let statement = *input;
+
+                    let len = counts(&data);
+
+                    let mut uvs2 = None;
+                    let faces = match &mut current.faces {
+                        Some(faces) => {
+                            match faces {
+                                Faces::V(list) => list.push(parse_face_v(len).parse_next(input)?),
+                                Faces::VT(list) => list.push(parse_face_vt(len).parse_next(input)?),
+                                Faces::VN(list) => list.push(parse_face_vn(len).parse_next(input)?),
+                                Faces::VTN(list) => {
+                                    let (face, face_uvs2) =
+                                        parse_face_vtn(len).parse_next(input)?;
+                                    list.push(face);
+                                    uvs2 = face_uvs2;
+                                }
+                            }
+                            faces
+                        }
+                        None => {
+                            let (faces, face_uvs2) = parse_face_start(input, len)?;
+                            uvs2 = face_uvs2;
+                            current.faces.insert(faces)
+                        }
+                    };
+
+                    let limits = totals.map_or(len, |totals| totals.max(len));
+                    let checked = check_last_face(faces, limits)
+                        .and_then(|()| match skipped.used_by_last(faces) {
+                            true => Err("face refers to a skipped element"),
+                            false => Ok(()),
+                        })
+                        .and_then(|()| push_uvs2(&mut current.uvs2, faces.len(), uvs2, limits));
+                    if let Err(message) = checked {
+                        // Leave no invalid face behind when the error is recovered from
+                        faces.pop();
+                        if faces.is_empty() {
+                            current.faces = None;
+                        }
+                        let error = WobjError::from(message);
+                        return Err(ContextError::from_external_error(&statement, error));
+                    }
+
+                    if warnings.enabled() && is_last_degenerate(faces) {
+                        warnings.push(statement, WarningKind::DegenerateFace);
+                    }
+
+                    if options.raw_indices {
+                        let mut raw_input = statement;
+                        current
+                            .raw_faces
+                            .push(parse_raw_face.parse_next(&mut raw_input)?);
+                    }
+                }
+                b"g" => {
+                    if split_on_attributes || split_on_groups {
+                        check(&mut current, split_on_groups);
+                    }
+                    let statement = *input;
+                    let names = parse_groups(utf8)
+                        .context(label("attribute group"))
+                        .parse_next(input)?;
+                    current.groups = names
+                        .iter()
+                        .map(|n| intern(&mut groups, n))
+                        .collect::<Result<_, _>>()
+                        .map_err(|e| ContextError::from_external_error(&statement, e))?;
+                    if split_on_groups {
+                        current.name = names.first().map(|name| Arc::from(&**name));
+                        skip_object.set(rejected(&current.name));
+                    }
+                }
+                b"s" => {
+                    let smoothing = parse_smoothing
+                        .context(label("attribute smoothing group"))
+                        .parse_next(input)?;
+                    match &current.faces {
+                        // Record the switch inside the current mesh
+                        Some(faces) if !split_on_attributes => {
+                            let start = faces.len();
+                            match current.smoothing_changes.last_mut() {
+                                Some(last) if last.0 == start => last.1 = smoothing,
+                                _ => current.smoothing_changes.push((start, smoothing)),
+                            }
+                        }
+                        _ => {
+                            check(&mut current, false);
+                            current.smoothing = smoothing;
+                        }
+                    }
+                }
+                b"o" => {
+                    check(&mut current, true);
+                    current.name = parse_str(utf8)
+                        .map(|name| name.map(Arc::from))
+                        .context(label("attribute object name"))
+                        .parse_next(input)?;
+                    skip_object.set(rejected(&current.name));
+                    comment_name = false;
+                }
+                b"shadow_obj" => {
+                    if let Some(path) = parse_path(utf8)
+                        .context(label("shadow object"))
+                        .parse_next(input)?
+                    {
+                        shadow_obj = Some(path);
+                    }
+                }
+                b"trace_obj" => {
+                    if let Some(path) = parse_path(utf8)
+                        .context(label("ray tracing object"))
+                        .parse_next(input)?
+                    {
+                        trace_obj = Some(path);
+                    }
+                }
+                b"mtllib" => {
+                    if split_on_attributes {
+                        check(&mut current, false);
+                    }
+                    if let Some(path) = parse_path(utf8)
+                        .context(label("attribute mtllib"))
+                        .parse_next(input)?
+                    {
+                        current.mtllib = Some(path);
+                    }
+                }
+                b"usemtl" => {
+                    if let Some(material) = parse_usemtl(utf8)
+                        .context(label("attribute material"))
+                        .parse_next(input)?
+                    {
+                        let material = material.map(Arc::from);
+                        use_material(&mut current, material, split_on_attributes, &mut check);
+                        comment_material = false;
+                    }
+                }
+                b"maplib" => {
+                    if split_on_attributes {
+                        check(&mut current, false);
+                    }
+                    if let Some(path) = parse_path(utf8)
+                        .context(label("attribute maplib"))
+                        .parse_next(input)?
+                    {
+                        current.maplib = Some(path);
+                    }
+                }
+                b"usemap" => {
+                    if split_on_attributes {
+                        check(&mut current, false);
+                    }
+                    if let Some(map) = parse_string(utf8)
+                        .context(label("attribute texture map"))
+                        .parse_next(input)?
+                    {
+                        current.usemap = (map != "off").then_some(map);
+                    }
+                }
+                b"cstype" => {
+                    (rational, cstype) = parse_cstype
+                        .context(label("free-form type"))
+                        .parse_next(input)?;
+                }
+                b"deg" => {
+                    degree = parse_degree
+                        .context(label("free-form degree"))
+                        .parse_next(input)?;
+                }
+                b"curv" | b"curv2" | b"surf" if skip_object.get() => {
+                    // Also drops the parameters and loops which follow
+                    current.free_forms.extend(current.free_form.take());
+                }
+                b"curv" | b"curv2" | b"surf" => {
+                    let statement = *input;
+                    let element = match key {
+                        b"curv" => parse_curv(counts(&data))
+                            .context(label("curve"))
+                            .parse_next(input),
+                        b"curv2" => parse_curv2(counts(&data))
+                            .context(label("2D curve"))
+                            .parse_next(input),
+                        _ => parse_surf(counts(&data))
+                            .context(label("surface"))
+                            .parse_next(input),
+                    }?
+                    .ok_or_else(|| {
+                        let error = WobjError::from("free-form index is out of range");
+                        ContextError::from_external_error(&statement, error)
+                    })?;
+
+                    current.free_forms.extend(current.free_form.take());
+                    current.free_form = Some(FreeForm {
+                        cstype: cstype.clone(),
+                        rational,
+                        degree,
+                        element,
+                        parm_u: Vec::new(),
+                        parm_v: Vec::new(),
+                        loops: Vec::new(),
+                        special_points: Vec::new(),
+                    });
+                }
+                b"parm" => {
+                    let (direction, values) = parse_parm
+                        .context(label("free-form parameters"))
+                        .parse_next(input)?;
+                    if let Some(free_form) = &mut current.free_form {
+                        match direction {
+                            'u' => free_form.parm_u = values,
+                            _ => free_form.parm_v = values,
+                        }
+                    }
+                }
+                b"trim" | b"hole" | b"scrv" => {
+                    let segments = parse_curve_loop
+                        .context(label("free-form curve loop"))
+                        .parse_next(input)?;
+                    if let Some(free_form) = &mut current.free_form {
+                        let kind = match key {
+                            b"trim" => CurveLoopKind::Trim,
+                            b"hole" => CurveLoopKind::Hole,
+                            _ => CurveLoopKind::Special,
+                        };
+                        free_form.loops.push(CurveLoop { kind, segments });
+                    }
+                }
+                b"sp" => {
+                    let statement = *input;
+                    let points = parse_index_list(counts(&data).param)
+                        .context(label("free-form special points"))
+                        .parse_next(input)?
+                        .ok_or_else(|| {
+                            let error = WobjError::from("free-form index is out of range");
+                            ContextError::from_external_error(&statement, error)
+                        })?;
+                    if let Some(free_form) = &mut current.free_form {
+                        free_form.special_points.extend(points);
+                    }
+                }
+                b"end" => current.free_forms.extend(current.free_form.take()),
+                b"call" if includes.enabled() => {
+                    let statement = *input;
+                    // Arguments for `$1`, `$2`... in the called file are ignored
+                    let mut path = BStr::new(word.context(label("call")).parse_next(input)?);
+                    let Some(path) = parse_path(utf8)
+                        .context(label("call"))
+                        .parse_next(&mut path)?
+                    else {
+                        return Ok(());
+                    };
+                    let called = includes
+                        .parse(&path, options)
+                        .map_err(|e| ContextError::from_external_error(&statement, e))?;
+
+                    let ids = called
+                        .groups
+                        .iter()
+                        .map(|name| intern(&mut groups, name))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| ContextError::from_external_error(&statement, e))?;
+
+                    // Called files become separate meshes, unless the object is rejected
+                    check(&mut current, false);
+                    let called_meshes = if skip_object.get() {
+                        Vec::new()
+                    } else {
+                        called.meshes
+                    };
+                    for mut mesh in called_meshes {
+                        mesh.offset(counts(&data));
+                        mesh.groups = mesh.groups.iter().map(|&id| ids[id as usize]).collect();
+                        check(&mut mesh, false);
+                    }
+                    data.append(called.data);
+                }
+                _ if options.strict => return Err(unknown_keyword(input, key)),
+                _ => {
+                    // Skip unknown keywords
+                    if warnings.enabled() {
+                        let key = String::from_utf8_lossy(key).into_owned();
+                        warnings.push(input, WarningKind::UnknownKeyword(key));
+                    }
+                }
+            }
+            Ok(())
+        })(input);
+
+        // Explain the common mistake of writing 0-based face indices
+        let result = result.map_err(|error| match key {
+            b"f" if has_zero_index(statement) => {
+                *input = statement;
+                ContextError::from_external_error(input, WobjError::from(ERROR_ZERO_INDEX))
+            }
+            _ => error,
+        });
+
+        match (result, &mut errors) {
+            (Ok(()), _) => (),
+            (Err(error), Some(errors)) => {
+                // Keep later indices pointing at the right elements, faces
+                // using the placeholders are skipped
+                let index = counts(&data);
+                match key {
+                    b"v" => {
+                        skipped.vertex.insert(index.vertex);
+                        data.vertex.push([0.0; 3]);
+                    }
+                    b"vn" => {
+                        skipped.normal.insert(index.normal);
+                        data.normal.push([0.0; 3]);
+                    }
+                    b"vt" => {
+                        skipped.texture.insert(index.texture);
+                        data.texture.push([0.0; 2]);
+                        if options.uv_w {
+                            data.texture_w.push(0.0);
+                        }
+                    }
+                    b"vp" => data.param.push([0.0; 3]),
+                    _ => (),
+                }
+
+                // Skip the statement and continue on the next line
+                let line = lines.line(statement);
+                errors.push(WobjError::with_context(
+                    format!("line {line}: {error}"),
+                    &error,
+                ));
+                *input = statement;
+            }
+            (Err(error), None) => return Err(error),
+        }
+
+        to_next_line(input)?;
+
+        statements += 1;
+        if let Some(progress) = &mut progress
+            && statements.is_multiple_of(PROGRESS_INTERVAL)
+        {
+            progress(total - input.len(), total);
+        }
+    }
+
+    if let Some(progress) = &mut progress {
+        progress(total, total);
+    }
+    if let Some(line_count) = line_count {
+        // Counted from the line breaks, the last line may be unterminated
+        let breaks = lines.line(input) - 1;
+        let unterminated = !source.is_empty() && !matches!(source.last(), Some(b'\n' | b'\r'));
+        *line_count = breaks + usize::from(unterminated);
+    }
+
+    check(&mut current, true);
+
+    Ok(Obj {
+        data,
+        groups,
+        meshes,
+        comments,
+        shadow_obj,
+        trace_obj,
+    })
+}
+
+/// Parses statements one by one, reporting them without building an `Obj`
+pub(super) fn parse_events(
+    input: &mut &BStr,
+    callback: &mut dyn FnMut(ObjEvent<'_>),
+) -> Result<()> {
+    skip_bom(input)?;
+    let utf8 = Utf8::Checked(InvalidUtf8::default());
+    let mut len = Counts::default();
+    // Reused between faces
+    let mut points = Vec::new();
+
     while let Ok(key) = keyword(input) {
         match key {
-            b"v" => data.vertex.push(
-                parse_float3
+            b"v" => {
+                let vertex = parse_float3
                     .context(label("vertex geometry"))
-                    .parse_next(input)?,
-            ),
-            b"vn" => data.normal.push(
-                parse_float3
+                    .parse_next(input)?;
+                len.vertex += 1;
+                callback(ObjEvent::Vertex(vertex));
+            }
+            b"vn" => {
+                let normal = parse_float3
                     .context(label("vertex normal"))
-                    .parse_next(input)?,
-            ),
-            b"vt" => data.texture.push(
-                parse_vt
+                    .parse_next(input)?;
+                len.normal += 1;
+                callback(ObjEvent::Normal(normal));
+            }
+            b"vt" => {
+                let [u, v, _] = parse_vt
                     .context(label("vertex texture"))
-                    .parse_next(input)?,
-            ),
-            b"f" => match &mut current.faces {
-                Some(faces) => match faces {
-                    Faces::V(list) => list.push(parse_face_v(&data).parse_next(input)?),
-                    Faces::VT(list) => list.push(parse_face_vt(&data).parse_next(input)?),
-                    Faces::VN(list) => list.push(parse_face_vn(&data).parse_next(input)?),
-                    Faces::VTN(list) => list.push(parse_face_vtn(&data).parse_next(input)?),
-                },
-                None => current.faces = Some(parse_face_start(input, &data)?),
-            },
+                    .parse_next(input)?;
+                len.texture += 1;
+                callback(ObjEvent::Uv([u, v]));
+            }
+            b"f" => {
+                let statement = *input;
+                parse_face_points(input, len, &mut points).map_err(|message| {
+                    let message = match has_zero_index(statement) {
+                        true => ERROR_ZERO_INDEX,
+                        false => message,
+                    };
+                    *input = statement;
+                    ContextError::from_external_error(input, WobjError::from(message))
+                })?;
+                callback(ObjEvent::Face(&points));
+            }
+            b"o" => {
+                if let Some(name) = parse_str(utf8)
+                    .context(label("attribute object name"))
+                    .parse_next(input)?
+                {
+                    callback(ObjEvent::Object(&name));
+                }
+            }
             b"g" => {
-                check(&mut current);
-                current.groups = parse_groups
+                let names = parse_groups(utf8)
                     .context(label("attribute group"))
                     .parse_next(input)?;
+                callback(ObjEvent::Groups(&names));
             }
             b"s" => {
-                check(&mut current);
-                current.smoothing = parse_smoothing
+                let smoothing = parse_smoothing
                     .context(label("attribute smoothing group"))
                     .parse_next(input)?;
-            }
-            b"o" => {
-                check(&mut current);
-                current.name = Some(
-                    parse_string
-                        .context(label("attribute object name"))
-                        .parse_next(input)?,
-                );
+                callback(ObjEvent::Smoothing(smoothing));
             }
             b"mtllib" => {
-                check(&mut current);
-                current.mtllib = Some(
-                    parse_path
-                        .context(label("attribute mtllib"))
-                        .parse_next(input)?,
-                );
+                if let Some(path) = parse_path(utf8)
+                    .context(label("attribute mtllib"))
+                    .parse_next(input)?
+                {
+                    callback(ObjEvent::MtlLib(&path));
+                }
             }
             b"usemtl" => {
-                check(&mut current);
-                current.material = Some(
-                    parse_string
-                        .context(label("attribute material"))
-                        .parse_next(input)?,
-                );
+                if let Some(material) = parse_usemtl(utf8)
+                    .context(label("attribute material"))
+                    .parse_next(input)?
+                {
+                    callback(ObjEvent::UseMtl(material.as_deref()));
+                }
             }
-            _ => (), // Skip unknown keywords
+            _ => (),
         }
-
         to_next_line(input)?;
     }
 
-    if current.faces.is_some() {
-        meshes.push(current);
+    Ok(())
+}
+
+/// Parses the points of a face into `points`, resolving and checking the indices
+fn parse_face_points(
+    input: &mut &BStr,
+    len: Counts,
+    points: &mut Vec<FacePoint<usize>>,
+) -> core::result::Result<(), &'static str> {
+    const ERROR_FACE: &str = "expected 3 or more face points";
+
+    // Secondary uvs are not reported
+    let mut point = terminated(parse_raw_point, opt(preceded('/', parse_raw_index)));
+    points.clear();
+    let mut next = point.parse_next(input).map_err(|_| ERROR_FACE)?;
+    loop {
+        let resolve = |i: Option<NonZero<isize>>, len, error| match i {
+            Some(i) => resolve_index(i, len).map(Some).ok_or(error),
+            None => Ok(None),
+        };
+        let resolved = FacePoint {
+            v: resolve_index(next.v, len.vertex).ok_or("vertex index is out of range")?,
+            t: resolve(next.t, len.texture, "uv index is out of range")?,
+            n: resolve(next.n, len.normal, "normal index is out of range")?,
+        };
+        if let Some(first) = points.first()
+            && (first.t.is_some(), first.n.is_some())
+                != (resolved.t.is_some(), resolved.n.is_some())
+        {
+            return Err("face points mix index formats");
+        }
+        points.push(resolved);
+
+        match opt(preceded(space1, point.by_ref())).parse_next(input) {
+            Ok(Some(point)) => next = point,
+            _ => break,
+        }
+    }
+
+    match points.len() >= 3 {
+        true => Ok(()),
+        false => Err(ERROR_FACE),
     }
+}
 
-    Ok(Obj { data, meshes })
+/// Parses the material name of a `usemtl` statement
+///
+/// Exporters write a bare `usemtl` or a placeholder name for no material,
+/// which parses as `Some(None)`. Returns `None` if the statement should be skipped.
+fn parse_usemtl<'a>(
+    utf8: Utf8,
+) -> impl Parser<&'a BStr, Option<Option<Cow<'a, str>>>, ContextError> {
+    const NO_MATERIAL: [&str; 3] = ["(null)", "None", "off"];
+
+    alt((
+        peek(till_comment.verify(|s: &[u8]| s.trim_ascii().is_empty())).value(Some(None)),
+        parse_str(utf8).map(|name| match name {
+            Some(name) if NO_MATERIAL.contains(&&*name) => Some(None),
+            name => name.map(Some),
+        }),
+    ))
+}
+
+/// Switches the material of the current mesh
+fn use_material(
+    current: &mut MeshData,
+    material: Option<Arc<str>>,
+    split_on_attributes: bool,
+    check: &mut impl FnMut(&mut MeshData, bool),
+) {
+    match &current.faces {
+        // Record the switch inside the current mesh
+        Some(faces) if !split_on_attributes => {
+            let start = faces.len();
+            match current.material_changes.last_mut() {
+                Some(last) if last.0 == start => last.1 = material,
+                _ => current.material_changes.push((start, material)),
+            }
+        }
+        _ => {
+            check(current, false);
+            current.material = material;
+        }
+    }
+}
+
+/// Parses the next comment line, if there is one, returning its start and text
+fn next_comment<'a>(input: &mut &'a BStr) -> Result<Option<(&'a [u8], &'a [u8])>> {
+    multispace0.parse_next(input)?;
+    let start = *input;
+    let comment = opt(delimited('#', till_line_end, opt(line_end))).parse_next(input)?;
+    Ok(comment.map(|comment| (start.as_ref(), comment)))
+}
+
+/// Parses a `<keyword> <value>` statement from comment text
+fn comment_statement(comment: &[u8], utf8: Utf8) -> Option<(&[u8], Cow<'_, str>)> {
+    preceded(space0, separated_pair(word, space1, parse_str(utf8)))
+        .parse(BStr::new(comment))
+        .ok()
+        .and_then(|(key, value)| Some((key, value?)))
+}
+
+/// Counts the vertex data statements without parsing them
+fn count_data(mut input: &BStr) -> Counts {
+    let mut counts = Counts::default();
+    while let Ok(key) = keyword(&mut input) {
+        match key {
+            b"v" => counts.vertex += 1,
+            b"vt" => counts.texture += 1,
+            b"vn" => counts.normal += 1,
+            b"vp" => counts.param += 1,
+            _ => (),
+        }
+        if to_next_line(&mut input).is_err() {
+            break;
+        }
+    }
+    counts
 }
 
 fn keyword<'a>(input: &mut &'a BStr) -> Result<&'a [u8]> {
-    delimited(ignoreable, word, space1)
+    // The value may be missing for keywords at the end of the line
+    delimited(ignoreable, word, alt((space1, peek(line_end), eof)))
         .context(label("keyword"))
         .parse_next(input)
 }
 
+/// Rejects `nan` and `inf` values, which would corrupt any computation using them
+fn is_finite<const N: usize>(values: &[f32; N]) -> bool {
+    values.iter().all(|v| v.is_finite())
+}
+
 fn parse_float3(input: &mut &BStr) -> Result<[f32; 3]> {
     (float, space1, float, space1, float)
         .map(|(x, _, y, _, z)| [x, y, z])
+        .verify(is_finite)
         .context(expected("x y z"))
         .context(description("3 coordinates"))
         .parse_next(input)
 }
 
-fn parse_vt(input: &mut &BStr) -> Result<[f32; 2]> {
-    (float, opt(preceded(space1, float)))
-        .map(|(u, v)| [u, v.unwrap_or(0.0)])
-        .context(expected("u v"))
+fn parse_vt(input: &mut &BStr) -> Result<[f32; 3]> {
+    (
+        float,
+        opt(preceded(space1, float)),
+        opt(preceded(space1, float)),
+    )
+        .map(|(u, v, w)| [u, v.unwrap_or(0.0), w.unwrap_or(0.0)])
+        .verify(is_finite)
+        .context(expected("u v w"))
         .context(description("texture coordinates"))
         .parse_next(input)
 }
 
-fn parse_face_start(input: &mut &BStr, data: &VertexData) -> Result<Faces> {
+fn parse_vp(input: &mut &BStr) -> Result<[f32; 3]> {
+    (
+        float,
+        opt(preceded(space1, float)),
+        opt(preceded(space1, float)),
+    )
+        .map(|(u, v, w)| [u, v.unwrap_or(0.0), w.unwrap_or(0.0)])
+        .verify(is_finite)
+        .context(expected("u v w"))
+        .context(description("parameter space coordinates"))
+        .parse_next(input)
+}
+
+/// Parses the first face of a mesh, along with its secondary uvs if any
+fn parse_face_start(input: &mut &BStr, len: Counts) -> Result<(Faces, Option<Vec<usize>>)> {
     alt((
-        parse_face_vtn(data).map(|v: Vec<_>| Faces::VTN(vec![v])),
-        parse_face_vn(data).map(|v: Vec<_>| Faces::VN(vec![v])),
-        parse_face_vt(data).map(|v: Vec<_>| Faces::VT(vec![v])),
-        parse_face_v(data).map(|v: Vec<_>| Faces::V(vec![v])),
+        parse_face_vtn(len).map(|(v, uvs2)| (Faces::VTN(vec![v]), uvs2)),
+        parse_face_vn(len).map(|v: Vec<_>| (Faces::VN(vec![v]), None)),
+        parse_face_vt(len).map(|v: Vec<_>| (Faces::VT(vec![v]), None)),
+        parse_face_v(len).map(|v: Vec<_>| (Faces::V(vec![v]), None)),
     ))
     .parse_next(input)
 }
 
+/// Stores the secondary uvs of the last face, keeping them parallel to the faces
+fn push_uvs2(
+    uvs2: &mut Vec<Vec<usize>>,
+    face_count: usize,
+    last: Option<Vec<usize>>,
+    len: Counts,
+) -> core::result::Result<(), &'static str> {
+    match last {
+        Some(last) if uvs2.len() + 1 == face_count => {
+            if last.iter().any(|t| *t >= len.texture) {
+                return Err("uv index is out of range");
+            }
+            uvs2.push(last);
+            Ok(())
+        }
+        None if uvs2.is_empty() => Ok(()),
+        _ => Err("faces mix points with and without secondary uvs"),
+    }
+}
+
+/// Checks if the indices of the last face are in range of the data
+fn check_last_face(faces: &Faces, len: Counts) -> core::result::Result<(), &'static str> {
+    const ERROR_OOB_VERTEX: &str = "vertex index is out of range";
+    const ERROR_OOB_NORMAL: &str = "normal index is out of range";
+    const ERROR_OOB_UV: &str = "uv index is out of range";
+
+    fn check<T>(
+        faces: &[Vec<T>],
+        index: impl Fn(&T) -> usize,
+        len: usize,
+        error: &'static str,
+    ) -> core::result::Result<(), &'static str> {
+        match faces.last() {
+            Some(face) if face.iter().any(|p| index(p) >= len) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    let (v, t, n) = (len.vertex, len.texture, len.normal);
+    match faces {
+        Faces::V(f) => check(f, |v| *v, v, ERROR_OOB_VERTEX),
+        Faces::VT(f) => {
+            check(f, |(v, _)| *v, v, ERROR_OOB_VERTEX)?;
+            check(f, |(_, t)| *t, t, ERROR_OOB_UV)
+        }
+        Faces::VN(f) => {
+            check(f, |(v, _)| *v, v, ERROR_OOB_VERTEX)?;
+            check(f, |(_, n)| *n, n, ERROR_OOB_NORMAL)
+        }
+        Faces::VTN(f) => {
+            check(f, |(v, _, _)| *v, v, ERROR_OOB_VERTEX)?;
+            check(f, |(_, t, _)| *t, t, ERROR_OOB_UV)?;
+            check(f, |(_, _, n)| *n, n, ERROR_OOB_NORMAL)
+        }
+    }
+}
+
+/// Checks if the last face has less than 3 unique vertex positions
+/// Indices of the vertex data elements replaced by placeholders in lossy parsing
+#[derive(Default)]
+struct Skipped {
+    vertex: Set<usize>,
+    texture: Set<usize>,
+    normal: Set<usize>,
+}
+
+impl Skipped {
+    /// Whether the last face uses a skipped element
+    fn used_by_last(&self, faces: &Faces) -> bool {
+        let Some(face) = faces.len().checked_sub(1) else {
+            return false;
+        };
+        let contains = |set: &Set<usize>, i: Option<usize>| i.is_some_and(|i| set.contains(&i));
+        (0..faces.face_len(face)).any(|i| {
+            let point = faces.point(face, i);
+            contains(&self.vertex, Some(point.v))
+                || contains(&self.texture, point.t)
+                || contains(&self.normal, point.n)
+        })
+    }
+}
+
+fn is_last_degenerate(faces: &Faces) -> bool {
+    fn check<T>(faces: &[Vec<T>], v: impl Fn(&T) -> usize) -> bool {
+        faces.last().is_some_and(|face| {
+            let mut positions: Vec<usize> = face.iter().map(v).collect();
+            positions.sort_unstable();
+            positions.dedup();
+            positions.len() < 3
+        })
+    }
+
+    match faces {
+        Faces::V(faces) => check(faces, |v| *v),
+        Faces::VT(faces) => check(faces, |(v, _)| *v),
+        Faces::VN(faces) => check(faces, |(v, _)| *v),
+        Faces::VTN(faces) => check(faces, |(v, _, _)| *v),
+    }
+}
+
+const ERROR_ZERO_INDEX: &str = "OBJ indices are 1-based; index 0 is invalid";
+
+/// Whether the face points on the line contain a `0` index
+fn has_zero_index(mut line: &BStr) -> bool {
+    // A trailing comment may contain anything
+    let line = till_comment(&mut line).unwrap_or_default();
+    let mut indices = line.split(|b| matches!(b, b' ' | b'\t' | b'/'));
+    indices.any(|i| {
+        let digits = i.strip_prefix(b"-").or(i.strip_prefix(b"+")).unwrap_or(i);
+        !digits.is_empty() && digits.iter().all(|b| *b == b'0')
+    })
+}
+
 fn calc_index(i: NonZero<isize>, len: usize) -> usize {
     match i.is_positive() {
         // Get the zeroed index
         true => (i.get() - 1) as usize,
-        // Calculate from relative index
-        false => len.saturating_add_signed(i.get()),
+        // Calculate from relative index, pointing out of range if it is before the start
+        false => len.checked_add_signed(i.get()).unwrap_or(usize::MAX),
     }
 }
 
@@ -145,75 +1006,219 @@ fn parse_index<'a>(len: usize) -> impl Parser<&'a BStr, usize, ContextError> {
         .map(move |i| calc_index(i, len))
 }
 
-fn parse_face_v<'a>(data: &VertexData) -> impl Parser<&'a BStr, Vec<usize>, ContextError> {
-    separated(3.., parse_index(data.vertex.len()), space1)
+fn parse_face_v<'a>(len: Counts) -> impl Parser<&'a BStr, Vec<usize>, ContextError> {
+    separated(3.., parse_index(len.vertex), space1)
         .context(expected("v1 v2 v3 ..."))
         .context(description("3 or more vertex indicies"))
 }
 
-fn parse_face_vt<'a>(
-    data: &VertexData,
-) -> impl Parser<&'a BStr, Vec<(usize, usize)>, ContextError> {
+fn parse_face_vt<'a>(len: Counts) -> impl Parser<&'a BStr, Vec<(usize, usize)>, ContextError> {
     separated(
         3..,
-        separated_pair(
-            parse_index(data.vertex.len()),
-            '/',
-            parse_index(data.texture.len()),
-        ),
+        separated_pair(parse_index(len.vertex), '/', parse_index(len.texture)),
         space1,
     )
     .context(expected("v1/t1 v2/t2 v3/t3 ..."))
     .context(description("3 or more vertex and texture indicies"))
 }
 
-fn parse_face_vn<'a>(
-    data: &VertexData,
-) -> impl Parser<&'a BStr, Vec<(usize, usize)>, ContextError> {
+fn parse_face_vn<'a>(len: Counts) -> impl Parser<&'a BStr, Vec<(usize, usize)>, ContextError> {
     separated(
         3..,
-        separated_pair(
-            parse_index(data.vertex.len()),
-            "//",
-            parse_index(data.normal.len()),
-        ),
+        separated_pair(parse_index(len.vertex), "//", parse_index(len.normal)),
         space1,
     )
     .context(expected("v1//n1 v2//n2 v3//n3 ..."))
     .context(description("3 or more vertex and normal indicies"))
 }
 
-fn parse_face_vtn<'a>(
-    data: &VertexData,
-) -> impl Parser<&'a BStr, Vec<(usize, usize, usize)>, ContextError> {
+/// A VTN face with its optional secondary uvs
+type FaceVtn = (Vec<(usize, usize, usize)>, Option<Vec<usize>>);
+
+/// Parses a face with an optional fourth secondary uv index on every point
+fn parse_face_vtn<'a>(len: Counts) -> impl Parser<&'a BStr, FaceVtn, ContextError> {
     separated(
         3..,
         seq!(
-            parse_index(data.vertex.len()),
+            parse_index(len.vertex),
             _: '/',
-            parse_index(data.texture.len()),
+            parse_index(len.texture),
             _: '/',
-            parse_index(data.normal.len()),
+            parse_index(len.normal),
+            opt(preceded('/', parse_index(len.texture))),
         ),
         space1,
     )
+    .verify_map(|points: Vec<(usize, usize, usize, Option<usize>)>| {
+        let face = points.iter().map(|&(v, t, n, _)| (v, t, n)).collect();
+        match points.iter().map(|p| p.3).collect::<Option<Vec<_>>>() {
+            Some(uvs2) => Some((face, Some(uvs2))),
+            None if points.iter().all(|p| p.3.is_none()) => Some((face, None)),
+            // Either all points have a secondary uv or none
+            None => None,
+        }
+    })
     .context(expected("v1/t1/n1 v2/t2/n2 v3/t3/n3 ..."))
     .context(description("3 or more vertex, texture and normal indicies"))
 }
 
-fn parse_groups(input: &mut &BStr) -> Result<Vec<String>> {
+fn parse_raw_index(input: &mut &BStr) -> Result<NonZero<isize>> {
+    dec_int.verify_map(NonZero::new).parse_next(input)
+}
+
+fn parse_raw_point(input: &mut &BStr) -> Result<FacePoint<NonZero<isize>>> {
+    (
+        parse_raw_index,
+        opt(preceded(
+            '/',
+            (opt(parse_raw_index), opt(preceded('/', parse_raw_index))),
+        )),
+    )
+        .map(|(v, tn)| {
+            let (t, n) = tn.unwrap_or_default();
+            FacePoint { v, t, n }
+        })
+        .parse_next(input)
+}
+
+fn parse_raw_face(input: &mut &BStr) -> Result<Vec<FacePoint<NonZero<isize>>>> {
+    separated(3.., parse_raw_point, space1).parse_next(input)
+}
+
+/// Resolves an index, checking that it is in range
+fn resolve_index(i: NonZero<isize>, len: usize) -> Option<usize> {
+    Some(calc_index(i, len)).filter(|i| *i < len)
+}
+
+/// Parses a list of indices, resolving them against the data length
+fn parse_index_list<'a>(len: usize) -> impl Parser<&'a BStr, Option<Vec<usize>>, ContextError> {
+    separated(1.., parse_raw_index, space1)
+        .map(move |list: Vec<_>| list.into_iter().map(|i| resolve_index(i, len)).collect())
+}
+
+fn parse_cstype(input: &mut &BStr) -> Result<(bool, String)> {
+    (
+        opt(terminated("rat", space1)).map(|rat| rat.is_some()),
+        word.try_map(|s: &[_]| String::from_utf8(s.to_vec())),
+    )
+        .context(expected("[rat] type"))
+        .parse_next(input)
+}
+
+fn parse_degree(input: &mut &BStr) -> Result<[u32; 2]> {
+    (dec_uint, opt(preceded(space1, dec_uint)))
+        .map(|(u, v)| [u, v.unwrap_or(0)])
+        .context(expected("degu [degv]"))
+        .parse_next(input)
+}
+
+fn parse_range(input: &mut &BStr) -> Result<[f32; 2]> {
+    separated_pair(float, space1, float)
+        .map(|(a, b)| [a, b])
+        .parse_next(input)
+}
+
+fn parse_curv<'a>(len: Counts) -> impl Parser<&'a BStr, Option<FreeFormElement>, ContextError> {
+    separated_pair(parse_range, space1, parse_index_list(len.vertex))
+        .map(|(range, vertices)| {
+            Some(FreeFormElement::Curve {
+                range,
+                vertices: vertices?,
+            })
+        })
+        .context(expected("u0 u1 v1 v2 ..."))
+}
+
+fn parse_curv2<'a>(len: Counts) -> impl Parser<&'a BStr, Option<FreeFormElement>, ContextError> {
+    parse_index_list(len.param)
+        .map(|vertices| {
+            Some(FreeFormElement::Curve2 {
+                vertices: vertices?,
+            })
+        })
+        .context(expected("vp1 vp2 ..."))
+}
+
+fn parse_surf<'a>(len: Counts) -> impl Parser<&'a BStr, Option<FreeFormElement>, ContextError> {
+    let (v, t, n) = (len.vertex, len.texture, len.normal);
+    let point = parse_raw_point.map(move |p| {
+        Some(FacePoint {
+            v: resolve_index(p.v, v)?,
+            t: match p.t {
+                Some(i) => Some(resolve_index(i, t)?),
+                None => None,
+            },
+            n: match p.n {
+                Some(i) => Some(resolve_index(i, n)?),
+                None => None,
+            },
+        })
+    });
+
+    (
+        parse_range,
+        space1,
+        parse_range,
+        space1,
+        separated(1.., point, space1),
+    )
+        .map(|(s, _, t, _, points): (_, _, _, _, Vec<_>)| {
+            let points = points.into_iter().collect::<Option<_>>()?;
+            Some(FreeFormElement::Surface { s, t, points })
+        })
+        .context(expected("s0 s1 t0 t1 v1/vt1/vn1 ..."))
+}
+
+fn parse_parm(input: &mut &BStr) -> Result<(char, Vec<f32>)> {
+    separated_pair(
+        alt(('u', 'v')),
+        space1,
+        separated(1.., float::<_, f32, _>, space1),
+    )
+    .context(expected("u|v p1 p2 ..."))
+    .parse_next(input)
+}
+
+fn parse_curve_loop(input: &mut &BStr) -> Result<Vec<CurveSegment>> {
     separated(
         1..,
-        word.try_map(|s: &[_]| String::from_utf8(s.to_vec())),
+        separated_pair(parse_range, space1, dec_int)
+            .map(|(range, curve)| CurveSegment { range, curve }),
         space1,
     )
-    .context(expected("group1 group2 ..."))
-    .context(description("list of group names"))
+    .context(expected("u0 u1 curv2d ..."))
     .parse_next(input)
 }
 
-fn parse_smoothing(input: &mut &BStr) -> Result<u32> {
-    alt((dec_uint, "off".value(0)))
+/// Returns the id of the group name, adding it if it is new
+///
+/// Fails if the id does not fit in a `u32`.
+pub(super) fn intern(groups: &mut GroupNames, name: &str) -> Result<u32, WobjError> {
+    let id = match groups.get_index_of(name) {
+        Some(id) => id,
+        None => groups.insert_full(name.to_owned()).0,
+    };
+    u32::try_from(id).map_err(|_| "too many group names".into())
+}
+
+fn parse_groups<'a>(utf8: Utf8) -> impl Parser<&'a BStr, Vec<Cow<'a, str>>, ContextError> {
+    // Decode after splitting, so an invalid name fails instead of ending the list.
+    // A bare `g` resets to the default group, which has no names.
+    let name = word.verify(|name: &[u8]| name[0] != b'#');
+    separated(0.., name, space1)
+        .try_map(move |names: Vec<_>| {
+            let names = names.into_iter().map(|name| decode(name, utf8));
+            names
+                .filter_map(Result::transpose)
+                .collect::<Result<_, _>>()
+        })
+        .context(expected("group1 group2 ..."))
+        .context(description("list of group names"))
+}
+
+/// Group 0 is the same as 'off'
+fn parse_smoothing(input: &mut &BStr) -> Result<Option<NonZero<u32>>> {
+    alt((dec_uint.map(NonZero::new), "off".value(None)))
         .context(description("smoothing group number or 'off'"))
         .parse_next(input)
 }
@@ -221,6 +1226,7 @@ fn parse_smoothing(input: &mut &BStr) -> Result<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Warning;
 
     #[test]
     fn face_parsing() {
@@ -230,54 +1236,845 @@ mod tests {
         data.texture.append(&mut [[1.0, 2.0]].repeat(3));
 
         assert_eq!(
-            parse_face_start(&mut BStr::new("1 2 3"), &data).unwrap(),
+            parse_face_start(&mut BStr::new("1 2 3"), data.counts())
+                .unwrap()
+                .0,
             Faces::V(vec!(vec!(0, 1, 2)))
         );
         assert_eq!(
-            parse_face_start(&mut BStr::new("1/3 2/2 3/1"), &data).unwrap(),
+            parse_face_start(&mut BStr::new("1/3 2/2 3/1"), data.counts())
+                .unwrap()
+                .0,
             Faces::VT(vec!(vec!((0, 2), (1, 1), (2, 0))))
         );
         assert_eq!(
-            parse_face_start(&mut BStr::new("1//3 2//2 3//1"), &data).unwrap(),
+            parse_face_start(&mut BStr::new("1//3 2//2 3//1"), data.counts())
+                .unwrap()
+                .0,
             Faces::VN(vec!(vec!((0, 2), (1, 1), (2, 0))))
         );
         assert_eq!(
-            parse_face_start(&mut BStr::new("1/2/3 2/3/1 3/1/2"), &data).unwrap(),
+            parse_face_start(&mut BStr::new("1/2/3 2/3/1 3/1/2"), data.counts())
+                .unwrap()
+                .0,
             Faces::VTN(vec!(vec!((0, 1, 2), (1, 2, 0), (2, 0, 1))))
         );
         assert_eq!(
-            parse_face_start(&mut BStr::new("-1 -2 -3"), &data).unwrap(),
+            parse_face_start(&mut BStr::new("-1 -2 -3"), data.counts())
+                .unwrap()
+                .0,
             Faces::V(vec!(vec!(2, 1, 0)))
         );
 
-        assert!(parse_face_start(&mut BStr::new(" "), &data).is_err());
-        assert!(parse_face_start(&mut BStr::new("1"), &data).is_err());
-        assert!(parse_face_start(&mut BStr::new("1 2"), &data).is_err());
-        assert!(parse_face_start(&mut BStr::new("1 e 2"), &data).is_err());
-        assert!(parse_face_start(&mut BStr::new("1 2 /3"), &data).is_err());
-        assert!(parse_face_start(&mut BStr::new("1/2 2 3/2"), &data).is_err());
+        assert!(parse_face_start(&mut BStr::new(" "), data.counts()).is_err());
+        assert!(parse_face_start(&mut BStr::new("1"), data.counts()).is_err());
+        assert!(parse_face_start(&mut BStr::new("1 2"), data.counts()).is_err());
+        assert!(parse_face_start(&mut BStr::new("1 e 2"), data.counts()).is_err());
+        assert!(parse_face_start(&mut BStr::new("1 2 /3"), data.counts()).is_err());
+        assert!(parse_face_start(&mut BStr::new("1/2 2 3/2"), data.counts()).is_err());
 
         assert_ne!(
-            parse_face_start(&mut BStr::new("1 2 3"), &data).unwrap(),
+            parse_face_start(&mut BStr::new("1 2 3"), data.counts())
+                .unwrap()
+                .0,
             Faces::V(vec!(vec!(2, 1, 0)))
         );
     }
 
     #[test]
-    fn group_parsing() {
+    fn relative_indices_per_array() {
+        // More vertices than normals, and more normals than uvs
+        let data = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvt 1 1\n\
+            vn 0 0 1\nvn 0 1 0\nvn 1 0 0\n";
+        let face = |f: &[u8]| {
+            let obj = Obj::parse(&[&data[..], f].concat()).unwrap();
+            obj.meshes()[0].faces().clone()
+        };
+
+        assert_eq!(
+            face(b"f -1/-1/-1 1/-2/2 -3/1/-3\n"),
+            Faces::VTN(vec![vec![(3, 1, 2), (0, 0, 1), (1, 0, 0)]])
+        );
+        assert_eq!(
+            face(b"f -1/-1 -2/-2 -4/-1\n"),
+            Faces::VT(vec![vec![(3, 1), (2, 0), (0, 1)]])
+        );
         assert_eq!(
-            parse_groups.parse(BStr::new("group1")),
-            Ok(vec!["group1".to_string()])
+            face(b"f -1//-1 -2//-3 -4//-2\n"),
+            Faces::VN(vec![vec![(3, 2), (2, 0), (0, 1)]])
         );
+
+        // Relative to the data before the face, not the end of the file
         assert_eq!(
-            parse_groups.parse(BStr::new("group1 group2 group3")),
-            Ok(vec![
-                "group1".to_string(),
-                "group2".to_string(),
-                "group3".to_string()
+            face(b"f -1/-1/-1 -2/-2/-2 -3/-1/-3\nv 5 5 5\nvt 5 5\nvn 5 5 5\n"),
+            Faces::VTN(vec![vec![(3, 1, 2), (2, 0, 1), (1, 1, 0)]])
+        );
+
+        // Each component is range checked against its own array
+        assert!(Obj::parse(&[&data[..], b"f -1/-3/-1 -2/-1/-1 -3/-1/-1\n"].concat()).is_err());
+        assert!(Obj::parse(&[&data[..], b"f -1/-1/-4 -2/-1/-1 -3/-1/-1\n"].concat()).is_err());
+
+        let mut faces = Vec::new();
+        let bytes = [&data[..], b"f -1/-1/-1 1/-2/2 -3/1/-3\n"].concat();
+        Obj::parse_events(&bytes, |event| {
+            if let ObjEvent::Face(points) = event {
+                faces.extend(points.iter().map(|p| (p.v, p.t.unwrap(), p.n.unwrap())));
+            }
+        })
+        .unwrap();
+        assert_eq!(faces, [(3, 1, 2), (0, 0, 1), (1, 0, 0)]);
+    }
+
+    #[test]
+    fn param_vertex_parsing() {
+        assert_eq!(parse_vp.parse(BStr::new("0.5 0.25")), Ok([0.5, 0.25, 0.0]));
+        assert_eq!(parse_vp.parse(BStr::new("1 2 3")), Ok([1.0, 2.0, 3.0]));
+        assert!(parse_vp.parse(BStr::new("x")).is_err());
+
+        let obj = Obj::parse(b"vp 0.5 0.25\nvp 1\n").unwrap();
+        assert_eq!(obj.param_vertices(), [[0.5, 0.25, 0.0], [1.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn float_parsing() {
+        let obj = Obj::parse(b"v 1e-10 1.5E+3 -2.5e2\nvt 1E0 .5\nvn 0 0 1e0\n").unwrap();
+        assert_eq!(obj.vertices(), [[1e-10, 1500.0, -250.0]]);
+        assert_eq!(obj.uvs(), [[1.0, 0.5]]);
+        assert_eq!(obj.normals(), [[0.0, 0.0, 1.0]]);
+
+        for bytes in [
+            &b"v 0 0 0\nv 0 nan 0\n"[..],
+            b"v 0 0 0\nvn inf 0 0\n",
+            b"v 0 0 0\nvt 0 -infinity\n",
+            b"v 0 0 0\nvp NaN\n",
+        ] {
+            let error = Obj::parse(bytes).unwrap_err().to_string();
+            assert!(error.starts_with("parse error at line 2"), "{error}");
+        }
+    }
+
+    #[test]
+    fn lossy_parsing() {
+        let bytes =
+            b"v 0 0 0\nv 1 0 0\nv oops\nv 0 1 0\nf 1 2 4\nf 1 2 9\nvt x\nf 4 2 1\nf 1 3 4\n";
+        assert!(Obj::parse(bytes).is_err());
+
+        let (obj, errors) = Obj::parse_lossy(bytes, ParseOptions::default()).unwrap();
+        // The broken vertex keeps its place, so later indices stay aligned
+        assert_eq!(obj.vertices().len(), 4);
+        assert_eq!(obj.vertices()[3], [0.0, 1.0, 0.0]);
+        assert!(obj.vertices().iter().flatten().all(|c| c.is_finite()));
+        assert_eq!(obj.uvs().len(), 1);
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::V(vec![vec![0, 1, 3], vec![3, 1, 0]])
+        );
+
+        let lines: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(lines.len(), 4);
+        assert!(
+            lines[0].starts_with("line 3: invalid vertex geometry"),
+            "{}",
+            lines[0]
+        );
+        assert!(lines[1].starts_with("line 6: "), "{}", lines[1]);
+        assert!(
+            lines[2].starts_with("line 7: invalid vertex texture"),
+            "{}",
+            lines[2]
+        );
+        assert_eq!(errors[0].labels(), ["vertex geometry"]);
+        assert_eq!(errors[2].labels(), ["vertex texture"]);
+        // Faces using the skipped vertex are skipped too
+        assert!(
+            lines[3].starts_with("line 9: ") && lines[3].contains("skipped element"),
+            "{}",
+            lines[3]
+        );
+
+        // A broken first face does not leave an empty mesh behind
+        let (obj, errors) =
+            Obj::parse_lossy(b"v 0 0 0\nf 1 1 2\n", ParseOptions::default()).unwrap();
+        assert!(obj.meshes().is_empty());
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        for statement in [
+            &b"o \xFFname"[..],
+            b"g a \xFF",
+            b"usemtl \xFF",
+            b"cstype \xFF",
+        ] {
+            let bytes = [b"v 0 0 0\n", statement, b"\nf 1 1 1\n"].concat();
+            let error = Obj::parse(&bytes).unwrap_err().to_string();
+            assert!(error.starts_with("parse error at line 2"), "{error}");
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_modes() {
+        let bytes = b"v 0 0 0\no Na\xFFme\ng a \xFF b\nusemtl \xFF\nf 1 1 1\n";
+        let options = |mode| ParseOptions {
+            on_invalid_utf8: mode,
+            ..Default::default()
+        };
+
+        assert!(Obj::parse_with(bytes, options(InvalidUtf8::Error)).is_err());
+
+        let obj = Obj::parse_with(bytes, options(InvalidUtf8::Lossy)).unwrap();
+        let mesh = obj.meshes()[0];
+        assert_eq!(mesh.name(), Some("Na\u{FFFD}me"));
+        assert_eq!(
+            mesh.group_names().collect::<Vec<_>>(),
+            ["a", "\u{FFFD}", "b"]
+        );
+        assert_eq!(mesh.material(), Some("\u{FFFD}"));
+
+        let obj = Obj::parse_with(bytes, options(InvalidUtf8::Skip)).unwrap();
+        let mesh = obj.meshes()[0];
+        assert_eq!(mesh.name(), None);
+        assert_eq!(mesh.group_names().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(mesh.material(), None);
+    }
+
+    #[test]
+    fn line_endings() {
+        let lines = [
+            "# comment",
+            "v 0 0 0",
+            "v 1 0 0",
+            "v 0 1 0",
+            "o Triangle",
+            "g first second",
+            "unknown",
+            "f 1 2 3",
+            "end",
+        ];
+        for ending in ["\n", "\r\n", "\r"] {
+            let bytes = lines.join(ending);
+            let (obj, warnings) =
+                Obj::parse_verbose(bytes.as_bytes(), ParseOptions::default()).unwrap();
+            let mesh = obj.meshes()[0];
+            assert_eq!(mesh.name(), Some("Triangle"));
+            assert_eq!(mesh.group_names().collect::<Vec<_>>(), ["first", "second"]);
+            assert_eq!(mesh.faces().len(), 1);
+            assert_eq!(warnings[0].line, 7);
+        }
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        let obj = Obj::parse(
+            b"v 0 0 0 \nv 1 0 0\t\nv 0 1 0 \t\r\nvt 0.5 \nvt 0.5 1\t\n\
+            f 1/1 2/2 3/1 \nf 1/1 2/2 3/2\t\r\nf 3/1 2/2 1/1 \t \n",
+        )
+        .unwrap();
+        assert_eq!(
+            obj.vertices(),
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+        assert_eq!(obj.uvs(), [[0.5, 0.0], [0.5, 1.0]]);
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::VT(vec![
+                vec![(0, 0), (1, 1), (2, 0)],
+                vec![(0, 0), (1, 1), (2, 1)],
+                vec![(2, 0), (1, 1), (0, 0)],
             ])
         );
 
-        assert!(parse_groups.parse(BStr::new(" ")).is_err());
+        let obj =
+            Obj::parse(b"v 0 0 0\t\nvn 0 0 1 \no Padded \t\nusemtl Material \nf 1//1 1//1 1//1 \n")
+                .unwrap();
+        assert_eq!(obj.normals(), [[0.0, 0.0, 1.0]]);
+        let mesh = obj.meshes()[0];
+        assert_eq!(mesh.faces().len(), 1);
+        assert_eq!(mesh.name(), Some("Padded"));
+        assert_eq!(mesh.material(), Some("Material"));
+    }
+
+    #[test]
+    fn free_form_parsing() {
+        let mut bytes = String::new();
+        for y in 0..4 {
+            for x in 0..4 {
+                bytes += &format!("v {x} {y} 0\n");
+            }
+        }
+        bytes += "vp 0 0\nvp 1 0\nvp 1 1\nvp 0 1\nvp 0 0\n\
+            o Patch\n\
+            cstype bspline\ndeg 1\ncurv2 1 2 3 4 5\nparm u 0 1 2 3 4\nend\n\
+            cstype rat bspline\ndeg 3 3\n\
+            surf 0 1 0 1 13 14 15 16 9 10 11 12 5 6 7 8 1 2 3 4\n\
+            parm u 0 0 0 0 1 1 1 1\nparm v 0 0 0 0 1 1 1 1\n\
+            trim 0 4 -1\nsp 1 3\nend\n";
+
+        let obj = Obj::parse(bytes.as_bytes()).unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 1);
+        assert!(meshes[0].faces().is_empty());
+
+        let [curve, surface] = meshes[0].free_forms() else {
+            panic!("expected 2 free-form elements");
+        };
+        assert_eq!(
+            curve.element,
+            FreeFormElement::Curve2 {
+                vertices: vec![0, 1, 2, 3, 4]
+            }
+        );
+        assert_eq!((curve.rational, curve.degree), (false, [1, 0]));
+
+        assert_eq!(surface.cstype, "bspline");
+        assert_eq!((surface.rational, surface.degree), (true, [3, 3]));
+        let FreeFormElement::Surface { s, t, points } = &surface.element else {
+            panic!("expected a surface");
+        };
+        assert_eq!((*s, *t), ([0.0, 1.0], [0.0, 1.0]));
+        assert_eq!(points.len(), 16);
+        assert_eq!(points[0].v, 12);
+        assert_eq!(surface.parm_v, [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(surface.loops[0].kind, CurveLoopKind::Trim);
+        assert_eq!(
+            surface.loops[0].segments,
+            [CurveSegment {
+                range: [0.0, 4.0],
+                curve: -1
+            }]
+        );
+        assert_eq!(surface.special_points, [0, 2]);
+
+        assert!(Obj::parse(b"v 0 0 0\ncurv 0 1 1 2\nend\n").is_err());
+
+        // An element without `end` stays with its object
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\no A\ncurv 0 1 1 2\no B\nf 1 2 1\n").unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(
+            (meshes[0].name(), meshes[0].free_forms().len()),
+            (Some("A"), 1)
+        );
+        assert!(meshes[1].free_forms().is_empty());
+    }
+
+    #[test]
+    fn object_grouping() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+            o A\nusemtl M1\nf 1 2 3\nusemtl M2\nf 1 2 3\nf 1 2 3\n\
+            o B\nf 1 2 3\n";
+
+        let obj = Obj::parse(bytes).unwrap();
+        let meshes = obj.meshes();
+        let names: Vec<_> = meshes.iter().map(|m| m.name()).collect();
+        assert_eq!(names, [Some("A"), Some("A"), Some("B")]);
+
+        let options = ParseOptions {
+            grouping: Grouping::Object,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].faces().len(), 3);
+        assert_eq!(
+            meshes[0].material_ranges().collect::<Vec<_>>(),
+            [(Some("M1"), 0..1), (Some("M2"), 1..3)]
+        );
+        assert_eq!(
+            meshes[1].material_ranges().collect::<Vec<_>>(),
+            [(Some("M2"), 0..1)]
+        );
+    }
+
+    #[test]
+    fn clear_material() {
+        for clear in [
+            "usemtl",
+            "usemtl ",
+            "usemtl (null)",
+            "usemtl None",
+            "usemtl off",
+        ] {
+            let bytes = format!("v 0 0 0\nusemtl M\nf 1 1 1\n{clear}\nf 1 1 1\n");
+            let obj = Obj::parse(bytes.as_bytes()).unwrap();
+            let materials: Vec<_> = obj
+                .meshes()
+                .iter()
+                .map(|m| m.material().map(str::to_string))
+                .collect();
+            assert_eq!(materials, [Some("M".to_string()), None], "{clear}");
+
+            let mut events = Vec::new();
+            Obj::parse_events(bytes.as_bytes(), |event| {
+                if let ObjEvent::UseMtl(material) = event {
+                    events.push(material.map(str::to_string));
+                }
+            })
+            .unwrap();
+            assert_eq!(events, [Some("M".to_string()), None]);
+        }
+    }
+
+    #[test]
+    fn trailing_comments() {
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(
+            b"v 0 0 0 # origin\nv 1 0 0\nv 0 1 0\no Name # object\ng a b # groups\n\
+            usemtl Material#1 # material\nf 1 2 3 # comment\n",
+            options,
+        )
+        .unwrap();
+        let mesh = &obj.meshes()[0];
+        assert_eq!(mesh.name(), Some("Name"));
+        assert_eq!(mesh.material(), Some("Material#1"));
+        let groups: Vec<_> = mesh.groups().iter().map(|&g| obj.group_name(g)).collect();
+        assert_eq!(groups, ["a", "b"]);
+        assert_eq!(mesh.faces().len(), 1);
+    }
+
+    #[test]
+    fn group_grouping() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+            g Body\nusemtl Skin\nf 1 2 3\nusemtl Cloth\nf 1 2 3\n\
+            g Head Body\nf 1 2 3\n\
+            g Hat\nf 1 2 3\n";
+        let options = ParseOptions {
+            grouping: Grouping::Group,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let meshes = obj.meshes();
+        let names: Vec<_> = meshes.iter().map(|m| m.name()).collect();
+        assert_eq!(names, [Some("Body"), Some("Head"), Some("Hat")]);
+        assert_eq!(
+            meshes[0].material_ranges().collect::<Vec<_>>(),
+            [(Some("Skin"), 0..1), (Some("Cloth"), 1..2)]
+        );
+        assert_eq!(
+            meshes[1].group_names().collect::<Vec<_>>(),
+            ["Head", "Body"]
+        );
+
+        let options = ParseOptions {
+            grouping: Grouping::Object,
+            ..Default::default()
+        };
+        assert_eq!(Obj::parse_with(bytes, options).unwrap().meshes().len(), 1);
+    }
+
+    #[test]
+    fn smoothing_ranges() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+            o A\ns 1\nf 1 2 3\ns off\nusemtl M\nf 1 2 3\ns 2\nf 1 2 3\n\
+            o B\nf 1 2 3\n";
+        let options = ParseOptions {
+            grouping: Grouping::Object,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(
+            meshes[0].smoothing_ranges().collect::<Vec<_>>(),
+            [
+                (NonZero::new(1), 0..1),
+                (None, 1..2),
+                (NonZero::new(2), 2..3)
+            ]
+        );
+        assert_eq!(
+            meshes[0].material_ranges().collect::<Vec<_>>(),
+            [(None, 0..1), (Some("M"), 1..3)]
+        );
+        assert_eq!(
+            meshes[1].smoothing_ranges().collect::<Vec<_>>(),
+            [(NonZero::new(2), 0..1)]
+        );
+
+        // Split into separate meshes by default
+        let meshes = Obj::parse(bytes).unwrap().meshes().len();
+        assert_eq!(meshes, 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn texture_map_library() {
+        let obj = Obj::parse(include_bytes!("../../assets/maplib.obj")).unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].maplib(), Some("textures.map"));
+        assert_eq!(meshes[0].usemap(), Some("wood"));
+        assert_eq!(meshes[1].maplib(), Some("textures.map"));
+        assert_eq!(meshes[1].usemap(), None);
+    }
+
+    #[test]
+    fn comment_fallback() {
+        let bytes = b"# object Cube\nv 0 0 0\n# usemtl Mat\nf 1 1 1\n\
+            # object Plane\n# comment\nf 1 1 1\n\
+            o Real\n# object Fake\nf 1 1 1\n";
+        let options = ParseOptions {
+            comment_fallback: true,
+            ..Default::default()
+        };
+
+        let obj = Obj::parse(bytes).unwrap();
+        assert_eq!(obj.meshes().len(), 2);
+        assert_eq!(obj.meshes()[0].name(), None);
+
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let meshes = obj.meshes();
+        let names: Vec<_> = meshes.iter().map(|m| m.name()).collect();
+        assert_eq!(names, [Some("Cube"), Some("Plane"), Some("Real")]);
+        assert_eq!(meshes[0].material(), Some("Mat"));
+        assert_eq!(meshes[2].material(), Some("Mat"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn strict_parsing() {
+        let bytes = b"v 0 0 0\nvx 1 2 3\n";
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        assert!(Obj::parse(bytes).is_ok());
+        let error = Obj::parse_with(bytes, options).unwrap_err().to_string();
+        assert!(error.contains("line 2"));
+        assert!(error.contains("unknown keyword 'vx'"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn face_index_range() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\n";
+        let parse = |faces: &str| Obj::parse(format!("{vertices}{faces}").as_bytes());
+
+        assert!(parse("f 1 2 3\nf -1 -2 -3\n").is_ok());
+
+        let error = parse("f 99 1 2\n").unwrap_err().to_string();
+        assert!(error.contains("line 5"));
+        assert!(error.contains("vertex index is out of range"));
+
+        let error = parse("f 1 2 3\nf -4 1 2\n").unwrap_err().to_string();
+        assert!(error.contains("line 6"));
+        assert!(error.contains("vertex index is out of range"));
+
+        let error = parse("f 1/1 2/1 3/-2\n").unwrap_err().to_string();
+        assert!(error.contains("uv index is out of range"));
+    }
+
+    #[test]
+    fn forward_references() {
+        let bytes = b"vn 0 0 1\nf 1//1 2//1 3//1\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf -3//1 -2//1 -1//1\n";
+        assert!(Obj::parse(bytes).is_err());
+
+        let options = ParseOptions {
+            forward_references: true,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::VN(vec![vec![(0, 0), (1, 0), (2, 0)]; 2])
+        );
+        assert_eq!(obj.validate().out_of_range_indices, 0);
+
+        // Indices past the end of the file are still out of range
+        assert!(Obj::parse_with(b"f 1 2 4\nv 0 0 0\nv 1 0 0\nv 0 1 0\n", options).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn zero_face_index() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\n";
+        for faces in [
+            "f 0 1 2\n",
+            "f 1 2 3\nf 1/1 2/1 3/0\n",
+            "f 1 2 3\nf 1 2 0\n",
+        ] {
+            let bytes = format!("{vertices}{faces}");
+            let error = Obj::parse(bytes.as_bytes()).unwrap_err().to_string();
+            assert!(
+                error.contains("OBJ indices are 1-based; index 0 is invalid"),
+                "{error}"
+            );
+            assert!(error.contains(&format!("line {}", faces.lines().count() + 4)));
+
+            let error = Obj::parse_events(bytes.as_bytes(), |_| ()).unwrap_err();
+            assert!(error.to_string().contains("index 0 is invalid"), "{error}");
+        }
+
+        // A 0 in a trailing comment is not blamed
+        let bytes = format!("{vertices}f 1 2 4 # 0\n");
+        let error = Obj::parse(bytes.as_bytes()).unwrap_err().to_string();
+        assert!(!error.contains("index 0 is invalid"), "{error}");
+        let error = Obj::parse_events(bytes.as_bytes(), |_| ()).unwrap_err();
+        assert!(!error.to_string().contains("index 0 is invalid"), "{error}");
+    }
+
+    #[test]
+    fn warnings() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+            lod 2\nf 1 2 3\nf 1 1 2\n";
+
+        let (obj, warnings) = Obj::parse_verbose(bytes, ParseOptions::default()).unwrap();
+        assert_eq!(obj.meshes()[0].faces().len(), 2);
+        assert_eq!(
+            warnings,
+            [
+                Warning {
+                    line: 4,
+                    kind: WarningKind::UnknownKeyword("lod".to_string())
+                },
+                Warning {
+                    line: 6,
+                    kind: WarningKind::DegenerateFace
+                },
+            ]
+        );
+
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(Obj::parse_verbose(bytes, options).is_err());
+    }
+
+    #[test]
+    fn raw_indices() {
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 -2//-1 3//1\n";
+        let nz = |i| NonZero::new(i).unwrap();
+
+        let obj = Obj::parse(bytes).unwrap();
+        assert!(obj.meshes()[0].raw_faces().is_empty());
+
+        let obj = Obj::parse_raw_indices(bytes).unwrap();
+        let mesh = obj.meshes()[0];
+        assert_eq!(mesh.faces(), &Faces::VN(vec![vec![(0, 0), (1, 0), (2, 0)]]));
+        assert_eq!(
+            mesh.raw_faces()[0][1],
+            FacePoint {
+                v: nz(-2),
+                t: None,
+                n: Some(nz(-1))
+            }
+        );
+
+        let face = parse_raw_face.parse(BStr::new("-1 2/3 4/-5/6")).unwrap();
+        assert_eq!(face[0].v, nz(-1));
+        assert_eq!((face[1].t, face[1].n), (Some(nz(3)), None));
+        assert_eq!((face[2].t, face[2].n), (Some(nz(-5)), Some(nz(6))));
+    }
+
+    #[test]
+    fn group_parsing() {
+        assert_eq!(
+            parse_groups(Utf8::Checked(InvalidUtf8::Error)).parse(BStr::new("group1")),
+            Ok(vec!["group1".into()])
+        );
+        assert_eq!(
+            parse_groups(Utf8::Checked(InvalidUtf8::Error))
+                .parse(BStr::new("group1 group2 group3")),
+            Ok(vec!["group1".into(), "group2".into(), "group3".into()])
+        );
+
+        assert!(
+            parse_groups(Utf8::Checked(InvalidUtf8::Error))
+                .parse(BStr::new(" "))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn group_interning() {
+        let mut bytes = String::from("v 0 0 0\n");
+        for i in 0..1000 {
+            bytes += &format!("g shared part{}\nf 1 1 1\n", i % 4);
+        }
+
+        let obj = Obj::parse(bytes.as_bytes()).unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 1000);
+        assert_eq!(obj.groups.len(), 5);
+
+        assert_eq!(meshes[0].groups(), meshes[4].groups());
+        assert_eq!(meshes[0].groups()[0], meshes[1].groups()[0]);
+        assert_eq!(obj.group_name(meshes[999].groups()[1]), "part3");
+        assert_eq!(
+            meshes[2].group_names().collect::<Vec<_>>(),
+            ["shared", "part2"]
+        );
+    }
+
+    #[test]
+    fn smoothing_groups() {
+        let obj =
+            Obj::parse(b"v 0 0 0\nf 1 1 1\ns 3\nf 1 1 1\ns 0\nf 1 1 1\ns off\nf 1 1 1\n").unwrap();
+        let smoothing: Vec<_> = obj.meshes().iter().map(|m| m.smoothing()).collect();
+        assert_eq!(smoothing, [None, NonZero::new(3), None, None]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn shadow_and_trace_objects() {
+        let obj = Obj::parse(b"shadow_obj shadow.obj\ntrace_obj dir/trace.obj\nv 0 0 0\n").unwrap();
+        assert_eq!(obj.shadow_obj(), Some("shadow.obj"));
+        assert_eq!(obj.trace_obj(), Some("dir/trace.obj"));
+
+        let obj = Obj::parse(b"v 0 0 0\n").unwrap();
+        assert_eq!((obj.shadow_obj(), obj.trace_obj()), (None, None));
+
+        // A skipped path keeps the earlier one
+        let options = ParseOptions {
+            on_invalid_utf8: InvalidUtf8::Skip,
+            ..Default::default()
+        };
+        let bytes = b"shadow_obj a.obj\nshadow_obj \xFF.obj\ntrace_obj b.obj\ntrace_obj \xFF.obj\n";
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        assert_eq!(
+            (obj.shadow_obj(), obj.trace_obj()),
+            (Some("a.obj"), Some("b.obj"))
+        );
+    }
+
+    #[test]
+    fn capture_comments() {
+        let bytes = b"# Blender 4.2\nv 0 0 0\n\n  #  2 materials  \nf 1 1 1\n";
+        let options = ParseOptions {
+            capture_comments: true,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let comments: Vec<_> = obj.comments().iter().map(|c| (c.line, &*c.text)).collect();
+        assert_eq!(comments, [(1, "Blender 4.2"), (4, "2 materials")]);
+        assert_eq!(obj.meshes()[0].faces().len(), 1);
+
+        assert!(Obj::parse(bytes).unwrap().comments().is_empty());
+    }
+
+    #[test]
+    fn secondary_uvs() {
+        let head = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 1\nvn 0 0 1\n";
+        let obj = Obj::parse(&[&head[..], b"f 1/1/1/2 2/1/1/2 3/2/1/1\n"].concat()).unwrap();
+        let mesh = obj.meshes()[0];
+        assert_eq!(
+            mesh.faces(),
+            &Faces::VTN(vec![vec![(0, 0, 0), (1, 0, 0), (2, 1, 0)]])
+        );
+        assert_eq!(mesh.secondary_uvs(), Some(&[vec![1, 1, 0]][..]));
+
+        let obj = Obj::parse(&[&head[..], b"f 1/1/1 2/1/1 3/2/1\n"].concat()).unwrap();
+        assert_eq!(obj.meshes()[0].secondary_uvs(), None);
+
+        // Secondary uvs must be on all points and faces, and in range
+        assert!(Obj::parse(&[&head[..], b"f 1/1/1/2 2/1/1 3/2/1\n"].concat()).is_err());
+        assert!(
+            Obj::parse(
+                &[
+                    &head[..],
+                    b"f 1/1/1 2/1/1 3/2/1\nf 1/1/1/1 2/1/1/1 3/2/1/1\n"
+                ]
+                .concat()
+            )
+            .is_err()
+        );
+        assert!(Obj::parse(&[&head[..], b"f 1/1/1/3 2/1/1/1 3/2/1/1\n"].concat()).is_err());
+    }
+
+    #[test]
+    fn keep_empty_objects() {
+        let bytes = b"o Locator\nusemtl Mat\no Cube\nv 0 0 0\nf 1 1 1\nusemtl B\nf 1 1 1\no End\n";
+        let options = ParseOptions {
+            keep_empty_objects: true,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes, options).unwrap();
+        let meshes = obj.meshes();
+        let names: Vec<_> = meshes.iter().map(|m| m.name().unwrap()).collect();
+        assert_eq!(names, ["Locator", "Cube", "Cube", "End"]);
+        assert_eq!(meshes[0].material(), Some("Mat"));
+        assert!(meshes[0].faces().is_empty() && meshes[3].faces().is_empty());
+
+        assert_eq!(Obj::parse(bytes).unwrap().meshes().len(), 2);
+    }
+
+    #[test]
+    fn uv_w_coordinate() {
+        let bytes = b"vt 0.1 0.2 0.3\nvt 0.5\n";
+        let obj = Obj::parse(bytes).unwrap();
+        assert_eq!(obj.uvs(), [[0.1, 0.2], [0.5, 0.0]]);
+        assert!(obj.uvs_w().is_empty());
+
+        let options = ParseOptions {
+            uv_w: true,
+            ..Default::default()
+        };
+        let mut obj = Obj::parse_with(bytes, options).unwrap();
+        assert_eq!(obj.uvs(), [[0.1, 0.2], [0.5, 0.0]]);
+        assert_eq!(obj.uvs_w(), [0.3, 0.0]);
+
+        // Merged uvs without w get 0
+        obj.merge(Obj::parse(b"vt 1 1\n").unwrap()).unwrap();
+        assert_eq!(obj.uvs_w(), [0.3, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn truncated_input() {
+        // Every statement kind, cut and damaged at each position
+        let bytes = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0 1\nvn 0 0 1\nvp 0.5\n\
+            o A\ng a b\ns 1\nusemtl m\nf 1/1/1 2/1/1 3/1/1/1\nf -1 -2 -3\nl 1 2\np 1\n\
+            cstype rat bspline\ndeg 2\ncurv 0 1 1 2 3\nparm u 0 0 1 1\ntrim 0 1 1\nend\n\
+            surf 0 1 0 1 1 2 3 1\nend\ncon 1 0 1 1 2 0 1 1\n# object X\nmtllib a.mtl\n";
+        let options = ParseOptions {
+            raw_indices: true,
+            comment_fallback: true,
+            on_invalid_utf8: InvalidUtf8::Lossy,
+            ..Default::default()
+        };
+        for i in 0..bytes.len() {
+            let mut damaged = bytes.to_vec();
+            damaged[i] = b'0';
+            for input in [&bytes[..i], &damaged] {
+                let _ = Obj::parse_with(input, options);
+                let _ = Obj::parse_lossy(input, options);
+                let _ = Obj::parse_events(input, |_| ());
+            }
+        }
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let bytes = "\u{FEFF}v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let obj = Obj::parse_with(bytes.as_bytes(), options).unwrap();
+        assert_eq!(obj.vertices().len(), 3);
+        assert_eq!(obj.meshes()[0].faces().len(), 1);
+
+        let mut vertices = 0;
+        Obj::parse_events(bytes.as_bytes(), |event| {
+            vertices += matches!(event, ObjEvent::Vertex(_)) as usize
+        })
+        .unwrap();
+        assert_eq!(vertices, 3);
+    }
+
+    #[test]
+    fn default_group() {
+        let obj = Obj::parse(b"v 0 0 0\ng a b\nf 1 1 1\ng\nf 1 1 1\ng \nf 1 1 1\n").unwrap();
+        let meshes = obj.meshes();
+        assert_eq!(meshes.len(), 3);
+        assert_eq!(meshes[0].group_names().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(meshes[1].groups().is_empty());
+        assert!(meshes[2].groups().is_empty());
     }
 }
@@ -0,0 +1,406 @@
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+use super::{Indicies, Vertices};
+use crate::WobjError;
+use crate::compat::*;
+use crate::math;
+
+/// Error quadric of a vertex, the upper triangle of a symmetric 4x4 matrix
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Squared distance to the plane `n·p + d = 0`, scaled by `weight`
+    fn plane(n: [f32; 3], d: f32, weight: f64) -> Self {
+        let [a, b, c, d] = [n[0], n[1], n[2], d].map(f64::from);
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|q| q * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.0.iter_mut().zip(other.0).for_each(|(a, b)| *a += b);
+    }
+
+    /// Error of moving the vertex to `p`
+    fn error(&self, p: [f32; 3]) -> f64 {
+        let [x, y, z] = p.map(f64::from);
+        let q = &self.0;
+        let error = q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9];
+        error.max(0.0)
+    }
+}
+
+/// Edge collapses by cost, with the vertex versions they were planned at
+///
+/// Costs are never negative, so their bits sort like the values.
+type Queue = BinaryHeap<Reverse<(u64, usize, usize, u32, u32)>>;
+
+/// Planned collapse of the `remove` vertex into `keep`
+struct Collapse {
+    keep: usize,
+    remove: usize,
+    position: [f32; 3],
+    /// Weight of the removed vertex when interpolating the attributes
+    t: f32,
+}
+
+impl Vertices {
+    /// Reduces the triangle count to about `target_ratio` of the original
+    ///
+    /// Uses quadric error metric edge collapses, so flat areas are simplified
+    /// first. Normals and uvs of merged vertices are interpolated. Border and
+    /// attribute seam vertices are kept in place so the outline and the seams do
+    /// not tear, and collapses which would make the mesh non-manifold or flip
+    /// triangles are skipped.
+    ///
+    /// Fails without changes if the indices are not a triangle list, an index
+    /// is out of range or the attributes differ in length from the positions.
+    pub fn simplify(&mut self, indices: &mut Indicies, target_ratio: f32) -> Result<(), WobjError> {
+        self.check(indices)?;
+        if !indices.0.len().is_multiple_of(3) {
+            return Err("indices are not a triangle list".into());
+        }
+
+        let mut triangles: Vec<Option<[usize; 3]>> = indices
+            .0
+            .chunks_exact(3)
+            .map(|t| Some([t[0], t[1], t[2]]))
+            .collect();
+        let target = (triangles.len() as f32 * target_ratio.clamp(0.0, 1.0)) as usize;
+        let mut alive = triangles.len();
+
+        let count = self.positions.len();
+        let mut incident = vec![Vec::new(); count];
+        let mut quadrics = vec![Quadric::default(); count];
+        let mut edges: Map<[usize; 2], usize> = Map::default();
+        for (id, [a, b, c]) in triangles.iter().flatten().copied().enumerate() {
+            let [pa, pb, pc] = [a, b, c].map(|i| self.positions[i]);
+            let normal = math::cross(math::sub(pb, pa), math::sub(pc, pa));
+            let area = math::length(normal);
+            if area > 0.0 {
+                let n = math::scale(normal, 1.0 / area);
+                let plane = Quadric::plane(n, -math::dot(n, pa), f64::from(area));
+                for i in [a, b, c] {
+                    quadrics[i].add(&plane);
+                }
+            }
+            for (i, j) in [(a, b), (b, c), (c, a)] {
+                incident[i].push(id);
+                if i != j {
+                    *edges.entry([i.min(j), i.max(j)]).or_default() += 1;
+                }
+            }
+        }
+
+        // Vertices on borders, non-manifold edges and attribute seams stay put
+        let mut locked = vec![false; count];
+        for (&[a, b], &uses) in &edges {
+            if uses != 2 {
+                locked[a] = true;
+                locked[b] = true;
+            }
+        }
+        let mut by_position: Map<[u32; 3], usize> = Map::default();
+        for (i, p) in self.positions.iter().enumerate() {
+            if let Some(&other) = by_position.get(&p.map(f32::to_bits)) {
+                locked[i] = true;
+                locked[other] = true;
+            }
+            by_position.insert(p.map(f32::to_bits), i);
+        }
+
+        // Collapses ordered by error, invalidated when a vertex changes
+        let mut versions = vec![0u32; count];
+        let mut queue = Queue::new();
+        for &[a, b] in edges.keys() {
+            self.queue(&mut queue, &quadrics, &locked, &versions, [a, b]);
+        }
+
+        while alive > target {
+            let Some(Reverse((_, a, b, version_a, version_b))) = queue.pop() else {
+                break;
+            };
+            if versions[a] != version_a || versions[b] != version_b {
+                continue;
+            }
+            let Some((_, collapse)) = self.plan(&quadrics, &locked, a, b) else {
+                continue;
+            };
+            if !self.can_collapse(&triangles, &incident, &collapse) {
+                continue;
+            }
+
+            let Collapse {
+                keep,
+                remove,
+                position,
+                t,
+            } = collapse;
+            self.positions[keep] = position;
+            self.interpolate(keep, remove, t);
+            let removed = quadrics[remove];
+            quadrics[keep].add(&removed);
+
+            for id in core::mem::take(&mut incident[remove]) {
+                let Some(triangle) = &mut triangles[id] else {
+                    continue;
+                };
+                if triangle.contains(&keep) {
+                    triangles[id] = None;
+                    alive -= 1;
+                } else {
+                    triangle
+                        .iter_mut()
+                        .filter(|i| **i == remove)
+                        .for_each(|i| *i = keep);
+                    incident[keep].push(id);
+                }
+            }
+            incident[keep].retain(|&id| triangles[id].is_some());
+
+            versions[keep] += 1;
+            versions[remove] += 1;
+            for neighbour in neighbours(&triangles, &incident[keep], keep) {
+                self.queue(&mut queue, &quadrics, &locked, &versions, [keep, neighbour]);
+            }
+        }
+
+        // Drop the unused vertices
+        let mut remap = vec![usize::MAX; count];
+        let mut kept = Vec::new();
+        indices.0.clear();
+        for &[a, b, c] in triangles.iter().flatten() {
+            for i in [a, b, c] {
+                if remap[i] == usize::MAX {
+                    remap[i] = kept.len();
+                    kept.push(i);
+                }
+                indices.0.push(remap[i]);
+            }
+        }
+        self.positions = kept.iter().map(|&i| self.positions[i]).collect();
+        if let Some(normals) = &mut self.normals {
+            *normals = kept.iter().map(|&i| normals[i]).collect();
+        }
+        if let Some(uvs) = &mut self.uvs {
+            *uvs = kept.iter().map(|&i| uvs[i]).collect();
+        }
+        if let Some(uvs2) = &mut self.uvs2 {
+            *uvs2 = kept.iter().map(|&i| uvs2[i]).collect();
+        }
+        Ok(())
+    }
+
+    /// Queues the collapse of an edge with its current cost
+    fn queue(
+        &self,
+        queue: &mut Queue,
+        quadrics: &[Quadric],
+        locked: &[bool],
+        versions: &[u32],
+        [a, b]: [usize; 2],
+    ) {
+        if let Some((cost, _)) = self.plan(quadrics, locked, a, b) {
+            queue.push(Reverse((cost.to_bits(), a, b, versions[a], versions[b])));
+        }
+    }
+
+    /// Cheapest way to collapse the edge between `a` and `b`, if it may collapse
+    fn plan(
+        &self,
+        quadrics: &[Quadric],
+        locked: &[bool],
+        a: usize,
+        b: usize,
+    ) -> Option<(f64, Collapse)> {
+        let mut quadric = quadrics[a];
+        quadric.add(&quadrics[b]);
+
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let midpoint = math::scale(math::add(pa, pb), 0.5);
+        let candidates: &[(usize, usize, [f32; 3], f32)] = match (locked[a], locked[b]) {
+            (true, true) => return None,
+            (true, false) => &[(a, b, pa, 0.0)],
+            (false, true) => &[(b, a, pb, 0.0)],
+            (false, false) => &[(a, b, pa, 0.0), (b, a, pb, 0.0), (a, b, midpoint, 0.5)],
+        };
+        candidates
+            .iter()
+            .map(|&(keep, remove, position, t)| {
+                let collapse = Collapse {
+                    keep,
+                    remove,
+                    position,
+                    t,
+                };
+                (quadric.error(position), collapse)
+            })
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+    }
+
+    /// Whether the collapse keeps the mesh manifold and no triangle flips
+    fn can_collapse(
+        &self,
+        triangles: &[Option<[usize; 3]>],
+        incident: &[Vec<usize>],
+        collapse: &Collapse,
+    ) -> bool {
+        let (keep, remove) = (collapse.keep, collapse.remove);
+
+        // Only the vertices opposite the edge may neighbour both ends
+        let opposite = incident[remove]
+            .iter()
+            .filter_map(|&id| triangles[id])
+            .filter(|triangle| triangle.contains(&keep))
+            .count();
+        let keep_neighbours = neighbours(triangles, &incident[keep], keep);
+        let shared = neighbours(triangles, &incident[remove], remove)
+            .filter(|n| *n != keep && keep_neighbours.clone().any(|k| k == *n))
+            .collect::<Set<_>>()
+            .len();
+        if shared != opposite {
+            return false;
+        }
+
+        // Moved triangles must keep facing the same way
+        let moved = [(keep, &incident[keep]), (remove, &incident[remove])];
+        moved.into_iter().all(|(vertex, incident)| {
+            incident
+                .iter()
+                .filter_map(|&id| triangles[id])
+                .all(|triangle| {
+                    if triangle.contains(&keep) && triangle.contains(&remove) {
+                        return true;
+                    }
+                    let points = triangle.map(|i| self.positions[i]);
+                    let moved = triangle.map(|i| match i == vertex {
+                        true => collapse.position,
+                        false => self.positions[i],
+                    });
+                    let normal =
+                        |[a, b, c]: [[f32; 3]; 3]| math::cross(math::sub(b, a), math::sub(c, a));
+                    let (before, after) = (normal(points), normal(moved));
+                    math::dot(before, after) > 0.0
+                })
+        })
+    }
+
+    /// Blends the attributes of `remove` into `keep`
+    fn interpolate(&mut self, keep: usize, remove: usize, t: f32) {
+        if t == 0.0 {
+            return;
+        }
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        if let Some(normals) = &mut self.normals {
+            let blended = core::array::from_fn(|i| lerp(normals[keep][i], normals[remove][i]));
+            normals[keep] = math::normalize(blended);
+        }
+        for uvs in [&mut self.uvs, &mut self.uvs2].into_iter().flatten() {
+            uvs[keep] = core::array::from_fn(|i| lerp(uvs[keep][i], uvs[remove][i]));
+        }
+    }
+}
+
+/// Vertices sharing a triangle with `vertex`, may repeat
+fn neighbours<'a>(
+    triangles: &'a [Option<[usize; 3]>],
+    incident: &'a [usize],
+    vertex: usize,
+) -> impl Iterator<Item = usize> + Clone + 'a {
+    let triangles = incident.iter().filter_map(|&id| triangles[id]);
+    triangles.flatten().filter(move |&i| i != vertex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Indicies, Vertices};
+    use crate::Obj;
+
+    #[test]
+    fn simplify_plane() {
+        // Plane subdivided into 8x8 quads
+        let mut text = String::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                text += &format!("v {x} {y} 0\nvt {} {}\n", x as f32 / 8.0, y as f32 / 8.0);
+            }
+        }
+        for y in 0..8 {
+            for x in 0..8 {
+                let i = y * 9 + x + 1;
+                let [a, b, c, d] = [i, i + 1, i + 10, i + 9];
+                text += &format!("f {a}/{a} {b}/{b} {c}/{c} {d}/{d}\n");
+            }
+        }
+        let obj = Obj::parse(text.as_bytes()).unwrap();
+        let (mut indices, mut vertices) = obj.meshes()[0].triangulate().unwrap();
+        assert_eq!(indices.0.len(), 128 * 3);
+
+        vertices.simplify(&mut indices, 0.5).unwrap();
+        let triangles = indices.0.len() / 3;
+        assert!(triangles <= 64, "{triangles} triangles");
+        assert!(indices.0.iter().all(|&i| i < vertices.positions.len()));
+        assert_eq!(
+            vertices.uvs.as_ref().unwrap().len(),
+            vertices.positions.len()
+        );
+
+        // The plane keeps its outline and area, and faces the same way
+        let area: f32 = indices
+            .0
+            .chunks_exact(3)
+            .map(|t| {
+                let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices.positions[i]);
+                let normal = crate::math::cross(crate::math::sub(b, a), crate::math::sub(c, a));
+                assert!(normal[2] > 0.0);
+                normal[2] / 2.0
+            })
+            .sum();
+        assert!((area - 64.0).abs() < 1e-3, "{area}");
+
+        // Uvs still follow the positions on a flat plane
+        let uvs = vertices.uvs.unwrap();
+        for (p, uv) in vertices.positions.iter().zip(uvs) {
+            assert!((p[0] / 8.0 - uv[0]).abs() < 1e-5 && (p[1] / 8.0 - uv[1]).abs() < 1e-5);
+        }
+    }
+    #[test]
+    fn simplify_invalid() {
+        let mut vertices = Vertices {
+            positions: vec![[0.0; 3]; 3],
+            ..Default::default()
+        };
+        for indices in [vec![0, 1, 3], vec![0, 1]] {
+            let mut indices = Indicies(indices);
+            assert!(vertices.simplify(&mut indices, 0.5).is_err());
+        }
+
+        vertices.uvs = Some(vec![[0.0; 2]; 2]);
+        let mut indices = Indicies(vec![0, 1, 2]);
+        assert!(vertices.simplify(&mut indices, 0.5).is_err());
+        assert_eq!(indices.0, [0, 1, 2]);
+    }
+}
@@ -0,0 +1,271 @@
+use super::{Faces, Obj};
+use crate::compat::*;
+use crate::math;
+
+/// Coordinate system of the vertex data
+///
+/// Right-handed Y-up is the convention of the OBJ format, but exporters
+/// commonly write the coordinate system of their application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisSystem {
+    /// Y up, Z towards the viewer (OpenGL, Maya)
+    YUpRH,
+    /// Y up, Z away from the viewer (Direct3D, Unity)
+    YUpLH,
+    /// Z up, Y away from the viewer (Blender, 3ds Max)
+    ZUpRH,
+    /// Z up, Y towards the viewer
+    ZUpLH,
+}
+
+impl AxisSystem {
+    /// Source axis and sign of each right-handed Y-up component
+    const fn basis(self) -> [(usize, f32); 3] {
+        match self {
+            AxisSystem::YUpRH => [(0, 1.0), (1, 1.0), (2, 1.0)],
+            AxisSystem::YUpLH => [(0, 1.0), (1, 1.0), (2, -1.0)],
+            AxisSystem::ZUpRH => [(0, 1.0), (2, 1.0), (1, -1.0)],
+            AxisSystem::ZUpLH => [(0, 1.0), (2, 1.0), (1, 1.0)],
+        }
+    }
+
+    const fn is_right_handed(self) -> bool {
+        matches!(self, AxisSystem::YUpRH | AxisSystem::ZUpRH)
+    }
+
+    fn canonical(self, p: [f32; 3]) -> [f32; 3] {
+        self.basis().map(|(axis, sign)| p[axis] * sign)
+    }
+
+    fn local(self, p: [f32; 3]) -> [f32; 3] {
+        let mut out = [0.0; 3];
+        for (c, (axis, sign)) in p.into_iter().zip(self.basis()) {
+            out[axis] = c * sign;
+        }
+        out
+    }
+}
+
+impl Obj {
+    /// Converts the vertex positions and normals between coordinate systems
+    ///
+    /// Face winding is reversed when the handedness changes, so front faces
+    /// stay front facing. Parameter space vertices are not spatial and are
+    /// left unchanged, as are free-form elements.
+    pub fn convert_axes(&mut self, from: AxisSystem, to: AxisSystem) {
+        if from == to {
+            return;
+        }
+
+        let convert = |p: &mut [f32; 3]| *p = to.local(from.canonical(*p));
+        self.data.vertex.iter_mut().for_each(convert);
+        self.data.normal.iter_mut().for_each(convert);
+
+        if from.is_right_handed() != to.is_right_handed() {
+            self.reverse_winding();
+        }
+    }
+
+    /// Transforms the vertex positions and normals by a column-major matrix
+    ///
+    /// Positions are transformed as points and normals by the inverse-transpose
+    /// of the upper-left 3x3, then renormalized, so they stay perpendicular
+    /// under non-uniform scale. Face winding is reversed if the matrix mirrors.
+    pub fn apply_matrix(&mut self, m: [[f32; 4]; 4]) {
+        let [c0, c1, c2, t] = m.map(|c| [c[0], c[1], c[2]]);
+        for p in &mut self.data.vertex {
+            *p = [0, 1, 2].map(|i| c0[i] * p[0] + c1[i] * p[1] + c2[i] * p[2] + t[i]);
+        }
+
+        // Columns of the inverse-transpose, up to the determinant
+        let cofactor = [
+            math::cross(c1, c2),
+            math::cross(c2, c0),
+            math::cross(c0, c1),
+        ];
+        let det = math::dot(c0, cofactor[0]);
+        for n in &mut self.data.normal {
+            let scaled = [0, 1, 2].map(|i| {
+                let [a, b, c] = cofactor;
+                (a[i] * n[0] + b[i] * n[1] + c[i] * n[2]) * det.signum()
+            });
+            *n = math::normalize(scaled);
+        }
+
+        if det < 0.0 {
+            self.reverse_winding();
+        }
+    }
+
+    /// Reverses the face winding of all meshes
+    fn reverse_winding(&mut self) {
+        for mesh in &mut self.meshes {
+            if let Some(faces) = &mut mesh.faces {
+                faces.reverse_winding();
+            }
+            mesh.raw_faces.iter_mut().for_each(|face| face.reverse());
+            mesh.uvs2.iter_mut().for_each(|face| face.reverse());
+        }
+    }
+}
+
+impl Obj {
+    /// Axis aligned bounding box of the vertex positions as `(min, max)`
+    ///
+    /// Returns `None` if there are no vertices.
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let (first, rest) = self.data.vertex.split_first()?;
+        Some(rest.iter().fold((*first, *first), |(min, max), p| {
+            (
+                [0, 1, 2].map(|i| min[i].min(p[i])),
+                [0, 1, 2].map(|i| max[i].max(p[i])),
+            )
+        }))
+    }
+
+    /// Centers the vertex positions at the origin and scales them to fit in `[-1, 1]`
+    ///
+    /// Returns the applied translation and scale, each position was changed
+    /// to `(position + translation) * scale`. The scale is uniform, so the
+    /// normals stay valid.
+    pub fn normalize(&mut self) -> ([f32; 3], f32) {
+        let Some((min, max)) = self.bounds() else {
+            return ([0.0; 3], 1.0);
+        };
+
+        let translation = [0, 1, 2].map(|i| -(min[i] + max[i]) / 2.0);
+        let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0, f32::max);
+        let scale = if extent > 0.0 { 2.0 / extent } else { 1.0 };
+
+        for p in &mut self.data.vertex {
+            *p = [0, 1, 2].map(|i| (p[i] + translation[i]) * scale);
+        }
+
+        (translation, scale)
+    }
+
+    /// Scales all vertex normals to unit length
+    ///
+    /// Zero-length normals cannot be normalized. They are left unchanged and
+    /// their indices are returned.
+    pub fn normalize_normals(&mut self) -> Vec<usize> {
+        let mut zero = Vec::new();
+        for (i, n) in self.data.normal.iter_mut().enumerate() {
+            match math::length(*n) {
+                0.0 => zero.push(i),
+                length => *n = n.map(|c| c / length),
+            }
+        }
+        zero
+    }
+}
+
+impl Faces {
+    fn reverse_winding(&mut self) {
+        match self {
+            Faces::V(faces) => faces.iter_mut().for_each(|f| f.reverse()),
+            Faces::VT(faces) => faces.iter_mut().for_each(|f| f.reverse()),
+            Faces::VN(faces) => faces.iter_mut().for_each(|f| f.reverse()),
+            Faces::VTN(faces) => faces.iter_mut().for_each(|f| f.reverse()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math;
+
+    const TRIANGLE: &[u8] = b"v 0 0 0\nv 1 0 0\nv 1 2 3\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+
+    #[test]
+    fn yup_to_zup() {
+        let mut obj = Obj::parse(TRIANGLE).unwrap();
+        obj.convert_axes(AxisSystem::YUpRH, AxisSystem::ZUpRH);
+
+        assert_eq!(obj.vertices()[2], [1.0, -3.0, 2.0]);
+        assert_eq!(obj.normals()[0], [0.0, -1.0, 0.0]);
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::VN(vec![vec![(0, 0), (1, 0), (2, 0)]])
+        );
+
+        obj.convert_axes(AxisSystem::ZUpRH, AxisSystem::YUpRH);
+        assert_eq!(obj.vertices()[2], [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn normalize() {
+        let mut obj = Obj::parse(b"v 1 1 1\nv 5 2 1\nv 3 3 2\n").unwrap();
+        assert_eq!(obj.bounds(), Some(([1.0, 1.0, 1.0], [5.0, 3.0, 2.0])));
+
+        let (translation, scale) = obj.normalize();
+        assert_eq!((translation, scale), ([-3.0, -2.0, -1.5], 0.5));
+        assert_eq!(obj.bounds(), Some(([-1.0, -0.5, -0.25], [1.0, 0.5, 0.25])));
+        assert_eq!(obj.vertices()[2], [0.0, 0.5, 0.25]);
+
+        let mut empty = Obj::parse(b"").unwrap();
+        assert_eq!(empty.bounds(), None);
+        assert_eq!(empty.normalize(), ([0.0; 3], 1.0));
+    }
+
+    #[test]
+    fn normalize_normals() {
+        let mut obj = Obj::parse(b"vn 0 3 4\nvn 0 0 0\nvn 1 0 0\n").unwrap();
+        assert_eq!(obj.normalize_normals(), [1]);
+        assert_eq!(obj.normals(), [[0.0, 0.6, 0.8], [0.0; 3], [1.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn apply_matrix() {
+        let mut obj = Obj::parse(b"v 1 0 0\nvn 1 0 0\nf 1//1 1//1 1//1\n").unwrap();
+        // Rotate 90 degrees around Z, then translate along X
+        obj.apply_matrix([
+            [0.0, 1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [10.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_eq!(obj.vertices()[0], [10.0, 1.0, 0.0]);
+        assert_eq!(obj.normals()[0], [0.0, 1.0, 0.0]);
+
+        // Non-uniform scale keeps the normal perpendicular to the surface
+        let mut obj = Obj::parse(b"v 1 0 0\nv 0 1 0\nvn 1 1 0\n").unwrap();
+        obj.apply_matrix([
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let edge = math::sub(obj.vertices()[1], obj.vertices()[0]);
+        assert!(math::dot(edge, obj.normals()[0]).abs() < 1e-6);
+        assert!((math::length(obj.normals()[0]) - 1.0).abs() < 1e-6);
+
+        // Mirroring reverses the winding
+        let mut obj = Obj::parse(TRIANGLE).unwrap();
+        obj.apply_matrix([
+            [-1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let face_normal = obj.meshes()[0].face_normals()[0];
+        assert!(math::dot(face_normal, obj.normals()[0]) > 0.0);
+    }
+
+    #[test]
+    fn handedness_change() {
+        let mut obj = Obj::parse(TRIANGLE).unwrap();
+        obj.convert_axes(AxisSystem::YUpRH, AxisSystem::YUpLH);
+
+        assert_eq!(obj.vertices()[2], [1.0, 2.0, -3.0]);
+        assert_eq!(obj.normals()[0], [0.0, 0.0, -1.0]);
+        assert_eq!(
+            obj.meshes()[0].faces(),
+            &Faces::VN(vec![vec![(2, 0), (1, 0), (0, 0)]])
+        );
+        // The normal still agrees with the winding
+        let face_normal = obj.meshes()[0].face_normals()[0];
+        assert!(math::dot(face_normal, obj.normals()[0]) > 0.0);
+    }
+}
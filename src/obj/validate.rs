@@ -0,0 +1,118 @@
+use super::{FreeFormElement, Obj};
+use crate::compat::*;
+
+/// Problems found in the face data by [`Obj::validate`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Vertex positions not used by any face or free-form element
+    pub unreferenced_vertices: Vec<usize>,
+    /// Number of faces with less than 3 unique vertex positions
+    pub degenerate_faces: usize,
+    /// Edges shared by more than two faces, as sorted vertex position pairs
+    pub non_manifold_edges: Vec<[usize; 2]>,
+    /// Number of position, uv or normal indices pointing past their data
+    pub out_of_range_indices: usize,
+}
+
+impl ValidationReport {
+    /// Whether no problems were found
+    pub fn is_valid(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Obj {
+    /// Checks the faces of all meshes for common mesh problems
+    ///
+    /// Works on the face data directly, nothing is triangulated.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut referenced = vec![false; self.data.vertex.len()];
+        let mut edges: Map<[usize; 2], usize> = Map::default();
+
+        let mut reference = |v: usize, report: &mut ValidationReport| match referenced.get_mut(v) {
+            Some(used) => *used = true,
+            None => report.out_of_range_indices += 1,
+        };
+
+        for mesh in &self.meshes {
+            for face in mesh.faces.iter().flat_map(|faces| faces.iter_faces()) {
+                let face: Vec<_> = face.collect();
+                for point in &face {
+                    reference(point.v, &mut report);
+                    let t = point.t.filter(|t| *t >= self.data.texture.len());
+                    let n = point.n.filter(|n| *n >= self.data.normal.len());
+                    report.out_of_range_indices += t.iter().count() + n.iter().count();
+                }
+
+                let mut positions: Vec<_> = face.iter().map(|p| p.v).collect();
+                for (i, &a) in positions.iter().enumerate() {
+                    let b = positions[(i + 1) % positions.len()];
+                    if a != b {
+                        *edges.entry([a.min(b), a.max(b)]).or_default() += 1;
+                    }
+                }
+                positions.sort_unstable();
+                positions.dedup();
+                if positions.len() < 3 {
+                    report.degenerate_faces += 1;
+                }
+            }
+
+            for free_form in &mesh.free_forms {
+                match &free_form.element {
+                    FreeFormElement::Curve { vertices, .. } => {
+                        vertices.iter().for_each(|v| reference(*v, &mut report));
+                    }
+                    FreeFormElement::Surface { points, .. } => {
+                        points.iter().for_each(|p| reference(p.v, &mut report));
+                    }
+                    FreeFormElement::Curve2 { .. } => (),
+                }
+            }
+        }
+
+        report.unreferenced_vertices = referenced
+            .iter()
+            .enumerate()
+            .filter_map(|(v, used)| (!used).then_some(v))
+            .collect();
+        report.non_manifold_edges = edges
+            .into_iter()
+            .filter_map(|(edge, count)| (count > 2).then_some(edge))
+            .collect();
+        report.non_manifold_edges.sort_unstable();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Obj;
+
+    #[test]
+    fn validate() {
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 5 5 5\nf 1 2 3\n").unwrap();
+        let report = obj.validate();
+        assert_eq!(report.unreferenced_vertices, [3]);
+        assert_eq!(report.degenerate_faces, 0);
+        assert!(!report.is_valid());
+
+        let obj = Obj::parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 1 2\n").unwrap();
+        let report = obj.validate();
+        assert!(report.unreferenced_vertices.is_empty());
+        assert_eq!(report.degenerate_faces, 1);
+
+        // Three triangles sharing the edge 1-2
+        let obj = Obj::parse(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 -1 0\nv 0 0 1\nf 1 2 3\nf 2 1 4\nf 1 2 5\n",
+        )
+        .unwrap();
+        let report = obj.validate();
+        assert_eq!(report.non_manifold_edges, [[0, 1]]);
+        assert_eq!(report.out_of_range_indices, 0);
+
+        let cube = Obj::parse(include_bytes!("../../assets/cube.obj")).unwrap();
+        assert!(cube.validate().is_valid());
+    }
+}
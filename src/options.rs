@@ -0,0 +1,78 @@
+/// Parsing options
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// How OBJ faces are grouped into meshes
+    pub grouping: Grouping,
+    /// Return an error on unknown keywords instead of skipping them, and on
+    /// duplicate MTL material names instead of keeping the last one
+    pub strict: bool,
+    /// Keep OBJ face indices as written in addition to the resolved ones
+    pub raw_indices: bool,
+    /// Recover object names and materials from `# object <name>` and
+    /// `# usemtl <name>` comments when the real statements are missing
+    ///
+    /// This is a last-resort recovery for broken exports, comments never
+    /// override real `o` and `usemtl` statements.
+    pub comment_fallback: bool,
+    /// How names and paths which are not valid UTF-8 are handled
+    pub on_invalid_utf8: InvalidUtf8,
+    /// Keep OBJ comments with their line numbers, see `Obj::comments`
+    ///
+    /// Exporters often write metadata like their name and version in comments.
+    pub capture_comments: bool,
+    /// Keep named objects without any geometry as meshes without faces
+    ///
+    /// Scene tools export empties and locators as objects with only a name
+    /// and attributes. They are dropped by default.
+    pub keep_empty_objects: bool,
+    /// Keep the optional third `w` coordinate of `vt` statements, see `Obj::uvs_w`
+    ///
+    /// Only 3D textures use it, so it is dropped by default.
+    pub uv_w: bool,
+    /// Allow faces to refer to vertex data defined later in the file
+    ///
+    /// The OBJ format requires data to be defined before use, but some broken
+    /// exporters write faces first. Positive indices are then checked against
+    /// the data of the whole file, which is counted in an extra pass.
+    pub forward_references: bool,
+}
+
+/// Handling of names and paths which are not valid UTF-8
+///
+/// Some exporters write names in legacy encodings like Latin-1.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Fail parsing
+    #[default]
+    Error,
+    /// Replace invalid sequences with `U+FFFD`
+    Lossy,
+    /// Ignore the statement, or just the invalid name in lists like groups
+    Skip,
+}
+
+/// OBJ mesh grouping mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// Starts a new mesh whenever an `o`, `g`, `s`, `mtllib` or `usemtl`
+    /// statement is encountered after faces.
+    ///
+    /// Every mesh has a single set of attributes, but an object that switches
+    /// materials gets split into multiple meshes sharing the same name.
+    #[default]
+    Attributes,
+    /// Starts a new mesh only on `o` statements.
+    ///
+    /// Material and smoothing group switches inside an object are recorded as
+    /// face ranges (see `ObjMesh::material_ranges` and
+    /// `ObjMesh::smoothing_ranges`). Other attributes keep the last value set
+    /// within the object.
+    Object,
+    /// Starts a new mesh on `o` and `g` statements, naming it after the first
+    /// group name.
+    ///
+    /// For exporters like Maya which delimit objects with groups and do not
+    /// write `o` statements. Other attributes are handled like in
+    /// [`Grouping::Object`].
+    Group,
+}
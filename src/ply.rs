@@ -0,0 +1,138 @@
+use std::io::Write;
+
+use crate::{Obj, WobjError};
+
+/// PLY file encoding
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlyFormat {
+    /// Human readable text
+    #[default]
+    Ascii,
+    /// Binary little-endian
+    BinaryLittleEndian,
+}
+
+impl Obj {
+    /// Writes all meshes triangulated into a single PLY mesh
+    ///
+    /// Vertex normals and uvs (as `s`, `t`) are written when available.
+    pub fn write_ply<W: Write>(&self, mut w: W, format: PlyFormat) -> Result<(), WobjError> {
+        let (indices, vertices) = self.triangulate_all()?;
+        let normals = vertices.normals.as_deref();
+        let uvs = vertices.uvs.as_deref();
+
+        // Header
+        writeln!(w, "ply")?;
+        match format {
+            PlyFormat::Ascii => writeln!(w, "format ascii 1.0")?,
+            PlyFormat::BinaryLittleEndian => writeln!(w, "format binary_little_endian 1.0")?,
+        }
+        writeln!(w, "element vertex {}", vertices.positions.len())?;
+        for property in ["x", "y", "z"] {
+            writeln!(w, "property float {property}")?;
+        }
+        if normals.is_some() {
+            for property in ["nx", "ny", "nz"] {
+                writeln!(w, "property float {property}")?;
+            }
+        }
+        if uvs.is_some() {
+            for property in ["s", "t"] {
+                writeln!(w, "property float {property}")?;
+            }
+        }
+        writeln!(w, "element face {}", indices.0.len() / 3)?;
+        writeln!(w, "property list uchar uint vertex_indices")?;
+        writeln!(w, "end_header")?;
+
+        // Body
+        for (i, position) in vertices.positions.iter().enumerate() {
+            let mut values = position.to_vec();
+            if let Some(normals) = normals {
+                values.extend(normals[i]);
+            }
+            if let Some(uvs) = uvs {
+                values.extend(uvs[i]);
+            }
+
+            match format {
+                PlyFormat::Ascii => {
+                    let values: Vec<_> = values.iter().map(f32::to_string).collect();
+                    writeln!(w, "{}", values.join(" "))?;
+                }
+                PlyFormat::BinaryLittleEndian => {
+                    for value in values {
+                        w.write_all(&value.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        // Indices are written as uint
+        let indices = indices
+            .0
+            .into_iter()
+            .map(u32::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "too many vertices for u32 indices")?;
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            match format {
+                PlyFormat::Ascii => writeln!(w, "3 {a} {b} {c}")?,
+                PlyFormat::BinaryLittleEndian => {
+                    w.write_all(&[3])?;
+                    for index in [a, b, c] {
+                        w.write_all(&index.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads the element counts from the header and the size of the body
+    fn read_ply(bytes: &[u8]) -> (usize, usize, &[u8]) {
+        let end = b"end_header\n";
+        let split = bytes.windows(end.len()).position(|w| w == end).unwrap() + end.len();
+        let header = str::from_utf8(&bytes[..split]).unwrap();
+
+        let count = |element: &str| {
+            header
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("element {element} ")))
+                .map(|count| count.parse().unwrap())
+                .unwrap()
+        };
+
+        (count("vertex"), count("face"), &bytes[split..])
+    }
+
+    #[test]
+    fn write_ply() {
+        let obj = Obj::parse(include_bytes!("../assets/cube.obj")).unwrap();
+        let (_, vertices) = obj.triangulate_all().unwrap();
+
+        let mut ascii = Vec::new();
+        obj.write_ply(&mut ascii, PlyFormat::Ascii).unwrap();
+        let (vertex_count, face_count, body) = read_ply(&ascii);
+        assert_eq!(vertex_count, vertices.positions.len());
+        assert_eq!(face_count, 12);
+        let lines: Vec<_> = str::from_utf8(body).unwrap().lines().collect();
+        assert_eq!(lines.len(), vertex_count + face_count);
+        assert_eq!(lines[0].split(' ').count(), 8);
+
+        let mut binary = Vec::new();
+        obj.write_ply(&mut binary, PlyFormat::BinaryLittleEndian)
+            .unwrap();
+        let (vertex_count, face_count, body) = read_ply(&binary);
+        assert_eq!(vertex_count, vertices.positions.len());
+        assert_eq!(body.len(), vertex_count * 8 * 4 + face_count * (1 + 3 * 4));
+    }
+}
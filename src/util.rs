@@ -1,16 +1,43 @@
-use std::path::PathBuf;
+use core::str::Utf8Error;
 
-use winnow::ascii::{line_ending, multispace1, till_line_ending};
-use winnow::combinator::{alt, opt, preceded, repeat};
-use winnow::error::{StrContext, StrContextValue};
-use winnow::token::take_till;
+use winnow::ascii::multispace1;
+use winnow::combinator::{alt, opt, peek, preceded, repeat};
+use winnow::error::{ContextError, FromExternalError, StrContext, StrContextValue};
+use winnow::token::{take, take_till};
 use winnow::{BStr, Parser, Result};
 
+use crate::compat::*;
+use crate::{InvalidUtf8, WobjError};
+
 /// Go to next line
 pub fn to_next_line(input: &mut &BStr) -> Result<()> {
-    (till_line_ending, opt(line_ending))
-        .void()
-        .parse_next(input)
+    (till_line_end, opt(line_end)).void().parse_next(input)
+}
+
+/// Line ending of any platform: `\n`, `\r\n` or a lone `\r`
+pub fn line_end<'a>(input: &mut &'a BStr) -> Result<&'a [u8]> {
+    alt(("\r\n", "\n", "\r")).parse_next(input)
+}
+
+/// Rest of the line before the line ending
+pub fn till_line_end<'a>(input: &mut &'a BStr) -> Result<&'a [u8]> {
+    take_till(0.., ('\r', '\n')).parse_next(input)
+}
+
+/// Rest of the line before a trailing `#` comment
+///
+/// A `#` only starts a comment at the start or after whitespace, so names
+/// like `Material#1` are kept whole.
+pub fn till_comment<'a>(input: &mut &'a BStr) -> Result<&'a [u8]> {
+    let line = peek(till_line_end).parse_next(input)?;
+    let end = match line.first() {
+        Some(b'#') => 0,
+        _ => line
+            .windows(2)
+            .position(|w| w[0].is_ascii_whitespace() && w[1] == b'#')
+            .map_or(line.len(), |i| i + 1),
+    };
+    take(end).parse_next(input)
 }
 
 pub fn word<'a>(input: &mut &'a BStr) -> Result<&'a [u8]> {
@@ -29,29 +56,117 @@ pub fn description(text: &'static str) -> StrContext {
     StrContext::Expected(StrContextValue::Description(text))
 }
 
-/// Parses a non-empty UTF-8 string
-pub fn parse_string(input: &mut &BStr) -> Result<String> {
-    till_line_ending
+/// Converts input positions to 1-based line numbers
+///
+/// Positions must be queried in increasing order.
+pub struct LineCounter<'a> {
+    input: &'a [u8],
+    offset: usize,
+    line: usize,
+}
+
+impl<'a> LineCounter<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            offset: 0,
+            line: 1,
+        }
+    }
+
+    /// Line number at the start of `rest`, which must be a suffix of the input
+    pub fn line(&mut self, rest: &[u8]) -> usize {
+        let offset = self.input.len() - rest.len();
+        let newlines = (self.offset..offset)
+            .filter(|&i| match self.input[i] {
+                b'\n' => true,
+                b'\r' => self.input.get(i + 1) != Some(&b'\n'),
+                _ => false,
+            })
+            .count();
+        self.offset = offset;
+        self.line += newlines;
+        self.line
+    }
+}
+
+/// Error for keywords not recognized in strict mode
+pub fn unknown_keyword(input: &&BStr, key: &[u8]) -> ContextError {
+    let message = format!("unknown keyword '{}'", String::from_utf8_lossy(key));
+    ContextError::from_external_error(input, WobjError::from(message.as_str()))
+}
+
+/// Decoding of names and paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8 {
+    /// Validated, invalid text is handled as configured
+    Checked(InvalidUtf8),
+    /// Known to be valid since the input is a `&str`
+    Valid,
+}
+
+/// Decodes text, returning `None` if it should be skipped
+pub fn decode(bytes: &[u8], mode: Utf8) -> core::result::Result<Option<Cow<'_, str>>, Utf8Error> {
+    let mode = match mode {
+        Utf8::Checked(mode) => mode,
+        Utf8::Valid => {
+            debug_assert!(str::from_utf8(bytes).is_ok());
+            // SAFETY: the input is a `str` and the parsers only split it at
+            // ASCII bytes, which are never part of a multi-byte character
+            return Ok(Some(Cow::Borrowed(unsafe {
+                str::from_utf8_unchecked(bytes)
+            })));
+        }
+    };
+    match (str::from_utf8(bytes), mode) {
+        (Ok(text), _) => Ok(Some(Cow::Borrowed(text))),
+        (Err(error), InvalidUtf8::Error) => Err(error),
+        (Err(_), InvalidUtf8::Lossy) => Ok(Some(String::from_utf8_lossy(bytes))),
+        (Err(_), InvalidUtf8::Skip) => Ok(None),
+    }
+}
+
+/// Parses a non-empty string till the end of the line or a trailing comment
+///
+/// Returns `None` if the text is invalid and should be skipped.
+pub fn parse_str<'a>(mode: Utf8) -> impl Parser<&'a BStr, Option<Cow<'a, str>>, ContextError> {
+    till_comment
+        // Exporters may pad lines with whitespace
+        .map(<[u8]>::trim_ascii_end)
         .verify(|s: &[_]| !s.is_empty())
-        .try_map(|s: &[_]| String::from_utf8(s.to_vec()))
+        .try_map(move |s| decode(s, mode))
         .context(description("UTF-8 string"))
-        .parse_next(input)
 }
 
-/// Parses a non-empty filesystem path
-pub fn parse_path(input: &mut &BStr) -> Result<PathBuf> {
-    use std::path::{MAIN_SEPARATOR, MAIN_SEPARATOR_STR};
-    const OTHER_SEPARATOR: char = match MAIN_SEPARATOR {
-        '/' => '\\',
-        _ => '/',
-    };
+/// Parses a non-empty string till the end of the line
+pub fn parse_string<'a>(mode: Utf8) -> impl Parser<&'a BStr, Option<String>, ContextError> {
+    parse_str(mode).map(|s| s.map(Cow::into_owned))
+}
 
-    parse_string
-        .map(|s| s.replace("\\\\", "\\"))
-        .map(|s| s.replace(OTHER_SEPARATOR, MAIN_SEPARATOR_STR))
-        .map(PathBuf::from)
+/// Parses a non-empty filesystem path
+///
+/// Separators are normalized to `/`, which every platform accepts, so paths
+/// authored on Windows resolve elsewhere too. Local `file://` URIs are reduced
+/// to their path.
+pub fn parse_path<'a>(mode: Utf8) -> impl Parser<&'a BStr, Option<String>, ContextError> {
+    parse_str(mode)
+        .map(|s| s.map(|s| strip_file_uri(&s).replace("\\\\", "\\").replace('\\', "/")))
         .context(description("filesystem path"))
-        .parse_next(input)
+}
+
+/// Path of a local `file://` URI, like `file:///C:/textures/wood.png`
+fn strip_file_uri(path: &str) -> &str {
+    match path.strip_prefix("file://") {
+        // A drive letter follows the slash of the empty host
+        Some(rest) if rest.starts_with('/') && rest.as_bytes().get(2) == Some(&b':') => &rest[1..],
+        Some(rest) if rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+/// Skips the UTF-8 byte order mark which Windows editors write at the start of files
+pub fn skip_bom(input: &mut &BStr) -> Result<()> {
+    opt("\u{FEFF}").void().parse_next(input)
 }
 
 fn comment(input: &mut &BStr) -> Result<()> {
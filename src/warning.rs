@@ -0,0 +1,68 @@
+use crate::compat::*;
+use crate::util::LineCounter;
+
+/// Non-fatal issue found while parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// 1-based line number of the statement
+    pub line: usize,
+    /// What the issue was
+    pub kind: WarningKind,
+}
+
+/// Kind of parser warning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Statement with an unknown keyword was skipped
+    UnknownKeyword(String),
+    /// Face has less than 3 unique vertex positions
+    DegenerateFace,
+}
+
+impl core::fmt::Display for Warning {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            WarningKind::UnknownKeyword(key) => {
+                write!(f, "line {}: skipped unknown keyword '{key}'", self.line)
+            }
+            WarningKind::DegenerateFace => write!(f, "line {}: degenerate face", self.line),
+        }
+    }
+}
+
+/// Opt-in warning collection used by the parsers
+pub(crate) struct Warnings<'a> {
+    list: Option<&'a mut Vec<Warning>>,
+    lines: LineCounter<'a>,
+}
+
+impl<'a> Warnings<'a> {
+    /// Creates a collector that does nothing
+    pub fn disabled() -> Self {
+        Self {
+            list: None,
+            lines: LineCounter::new(&[]),
+        }
+    }
+
+    /// Creates a collector for the specified input
+    pub fn new(input: &'a [u8], list: &'a mut Vec<Warning>) -> Self {
+        Self {
+            list: Some(list),
+            lines: LineCounter::new(input),
+        }
+    }
+
+    /// Whether warnings are being collected
+    pub fn enabled(&self) -> bool {
+        self.list.is_some()
+    }
+
+    /// Adds a warning for the line at the start of `rest`
+    pub fn push(&mut self, rest: &[u8], kind: WarningKind) {
+        if let Some(list) = &mut self.list {
+            let line = self.lines.line(rest);
+            list.push(Warning { line, kind });
+        }
+    }
+}